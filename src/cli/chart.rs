@@ -0,0 +1,416 @@
+use serde_json::Value;
+
+/// Text rows of the plot area; each braille cell holds 2x4 dots
+const CHART_HEIGHT: usize = 10;
+
+/// Narrowest plot area drawn, even on very small terminals
+const MIN_CHART_COLUMNS: usize = 10;
+
+/// Widest sparkline in a table cell; longer series are averaged down to it
+pub const SPARK_WIDTH: usize = 30;
+
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Braille dot bits indexed by `[x % 2][y % 4]`, top row first
+const BRAILLE_DOTS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// One line of a chart: values in time order, nulls left as gaps
+#[derive(Debug, PartialEq)]
+pub struct Series {
+    pub label: String,
+    pub unit: Option<String>,
+    pub values: Vec<Option<f64>>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+}
+
+/// Pull chartable series out of a result
+///
+/// Metric rows carry their own `points.data`; flat timeseries rows (as from
+/// `logs timeseries`) are grouped by their non-numeric columns, with one series
+/// per compute labeled from `meta.computes`.
+pub fn series(result: &Value) -> Vec<Series> {
+    let rows = result
+        .get("data")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    if rows.iter().any(|row| row["points"]["data"].is_array()) {
+        rows.iter().filter_map(metric_series).collect()
+    } else if !rows.is_empty() && rows.iter().all(|row| row.get("timestamp").is_some()) {
+        bucket_series(rows, &result["meta"]["computes"])
+    } else {
+        Vec::new()
+    }
+}
+
+fn metric_series(row: &Value) -> Option<Series> {
+    let points = row["points"]["data"].as_array()?;
+
+    let name = row["metric"]
+        .as_str()
+        .or(row["name"].as_str())
+        .unwrap_or("series");
+    let group = match (&row["scope"], &row["group_tags"]) {
+        (Value::String(scope), _) if scope != "*" => scope.clone(),
+        (_, Value::Array(tags)) => tags
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(","),
+        _ => String::new(),
+    };
+    let label = if group.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} ({})", name, group)
+    };
+
+    let unit = ["short_name", "name"]
+        .iter()
+        .filter_map(|key| row["unit"][key].as_str())
+        .find(|unit| !unit.is_empty())
+        .map(String::from);
+
+    let timestamp = |point: Option<&Value>| point?["timestamp"].as_str().map(String::from);
+    Some(Series {
+        label,
+        unit,
+        values: points.iter().map(|p| p["value"].as_f64()).collect(),
+        start: timestamp(points.first()),
+        end: timestamp(points.last()),
+    })
+}
+
+/// Every series spans the union of bucket timestamps, so a group or compute
+/// missing from a bucket leaves a gap instead of shifting its later points.
+fn bucket_series(rows: &[Value], computes: &Value) -> Vec<Series> {
+    let compute_keys: Vec<&str> = match computes.as_object() {
+        Some(computes) => computes.keys().map(String::as_str).collect(),
+        None => {
+            let mut keys: Vec<&str> = Vec::new();
+            for row in rows.iter().filter_map(Value::as_object) {
+                for (key, value) in row {
+                    if value.is_number() && key != "timestamp" && !keys.contains(&key.as_str()) {
+                        keys.push(key);
+                    }
+                }
+            }
+            keys
+        }
+    };
+
+    let timestamp_of = |row: &Value| match &row["timestamp"] {
+        Value::String(t) => t.clone(),
+        other => other.to_string(),
+    };
+    let mut timestamps: Vec<String> = rows.iter().map(timestamp_of).collect();
+    timestamps.sort_by(|a, b| match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.total_cmp(&y),
+        _ => a.cmp(b),
+    });
+    timestamps.dedup();
+
+    let mut series: Vec<(String, Series)> = Vec::new();
+    for row in rows {
+        let Some(obj) = row.as_object() else { continue };
+        let group: Vec<String> = obj
+            .iter()
+            .filter(|(key, _)| *key != "timestamp" && !compute_keys.contains(&key.as_str()))
+            .map(|(key, value)| match value {
+                Value::String(s) => format!("{}={}", key, s),
+                other => format!("{}={}", key, other),
+            })
+            .collect();
+        let timestamp = timestamp_of(row);
+        let Some(slot) = timestamps.iter().position(|t| *t == timestamp) else {
+            continue;
+        };
+
+        for key in &compute_keys {
+            let label = computes[*key].as_str().unwrap_or(key);
+            let label = if group.is_empty() {
+                label.to_string()
+            } else {
+                format!("{} ({})", label, group.join(","))
+            };
+
+            let index = match series.iter().position(|(l, _)| *l == label) {
+                Some(index) => index,
+                None => {
+                    let line = Series {
+                        label: label.clone(),
+                        unit: None,
+                        values: vec![None; timestamps.len()],
+                        start: timestamps.first().cloned(),
+                        end: timestamps.last().cloned(),
+                    };
+                    series.push((label, line));
+                    series.len() - 1
+                }
+            };
+            series[index].1.values[slot] = row[*key].as_f64();
+        }
+    }
+
+    series.into_iter().map(|(_, line)| line).collect()
+}
+
+/// Draw a series as a braille line chart `width` characters wide
+///
+/// The y axis is labeled with the series' minimum and maximum in its unit and
+/// the x axis with its first and last timestamps.
+pub fn render(series: &Series, width: usize) -> String {
+    let mut out = format!("{}\n", series.label);
+
+    let present: Vec<f64> = series.values.iter().flatten().copied().collect();
+    if present.is_empty() {
+        out.push_str("  (no data)\n");
+        return out;
+    }
+    let min = present.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = present.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    let label = |v: f64| match &series.unit {
+        Some(unit) => format!("{} {}", format_number(v), unit),
+        None => format_number(v),
+    };
+    let (top, bottom) = (label(max), label(min));
+    let axis_width = top.chars().count().max(bottom.chars().count());
+    let columns = width.saturating_sub(axis_width + 2).max(MIN_CHART_COLUMNS);
+
+    let grid = plot(&series.values, min, max, columns);
+    for (i, line) in grid.iter().enumerate() {
+        let (tick, axis) = match i {
+            0 => (top.as_str(), '┤'),
+            i if i == CHART_HEIGHT - 1 => (bottom.as_str(), '┤'),
+            _ => ("", '│'),
+        };
+        let dots: String = line
+            .iter()
+            .map(|bits| char::from_u32(0x2800 + *bits as u32).unwrap_or(' '))
+            .collect();
+        out.push_str(&format!("{:>axis_width$} {}{}\n", tick, axis, dots));
+    }
+    out.push_str(&format!("{:>axis_width$} └{}\n", "", "─".repeat(columns)));
+
+    if let Some(start) = &series.start {
+        let end = series
+            .end
+            .as_deref()
+            .filter(|end| end != start)
+            .unwrap_or("");
+        let gap = (columns + 1).saturating_sub(start.chars().count() + end.chars().count());
+        let end = if gap == 0 { "" } else { end };
+        out.push_str(&format!(
+            "{:>axis_width$} {}{}{}\n",
+            "",
+            start,
+            " ".repeat(gap.max(1)),
+            end
+        ));
+    }
+    out
+}
+
+/// Plot values onto braille cells, joining consecutive points with lines
+fn plot(values: &[Option<f64>], min: f64, max: f64, columns: usize) -> Vec<Vec<u8>> {
+    let (width, height) = (columns * 2, CHART_HEIGHT * 4);
+    let mut grid = vec![vec![0u8; columns]; CHART_HEIGHT];
+    let mut set = |x: usize, y: usize| grid[y / 4][x / 2] |= BRAILLE_DOTS[x % 2][y % 4];
+
+    let last = values.len().saturating_sub(1).max(1) as f64;
+    let x_of = |i: usize| (i as f64 / last * (width - 1) as f64).round() as i64;
+    let y_of = |v: f64| {
+        if max == min {
+            (height / 2) as i64
+        } else {
+            ((max - v) / (max - min) * (height - 1) as f64).round() as i64
+        }
+    };
+
+    let mut previous: Option<(i64, i64)> = None;
+    for (i, value) in values.iter().enumerate() {
+        let Some(value) = value else {
+            previous = None;
+            continue;
+        };
+        let point = (x_of(i), y_of(*value));
+        let (from_x, from_y) = previous.unwrap_or(point);
+        let steps = (point.0 - from_x)
+            .abs()
+            .max((point.1 - from_y).abs())
+            .max(1);
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            let x = from_x as f64 + (point.0 - from_x) as f64 * t;
+            let y = from_y as f64 + (point.1 - from_y) as f64 * t;
+            set(x.round() as usize, y.round() as usize);
+        }
+        previous = Some(point);
+    }
+    grid
+}
+
+/// Sparkline of a metric row's `points.data`, for the table `spark` column
+pub fn spark(row: &Value) -> Option<String> {
+    let points = row["points"]["data"].as_array()?;
+    let values: Vec<Option<f64>> = points.iter().map(|p| p["value"].as_f64()).collect();
+    Some(sparkline(&values, SPARK_WIDTH))
+}
+
+/// Render values as block characters, averaging them down to at most `width`;
+/// gaps stay blank and a flat series sits mid-height
+pub fn sparkline(values: &[Option<f64>], width: usize) -> String {
+    let buckets = values.len().min(width);
+    let averaged: Vec<Option<f64>> = (0..buckets)
+        .map(|i| {
+            let bucket = &values[i * values.len() / buckets..(i + 1) * values.len() / buckets];
+            let present: Vec<f64> = bucket.iter().flatten().copied().collect();
+            (!present.is_empty()).then(|| present.iter().sum::<f64>() / present.len() as f64)
+        })
+        .collect();
+
+    let present = averaged.iter().flatten().copied();
+    let min = present.clone().fold(f64::INFINITY, f64::min);
+    let max = present.fold(f64::NEG_INFINITY, f64::max);
+    let top = (SPARK_LEVELS.len() - 1) as f64;
+
+    averaged
+        .iter()
+        .map(|value| match value {
+            None => ' ',
+            Some(_) if max == min => SPARK_LEVELS[SPARK_LEVELS.len() / 2 - 1],
+            Some(v) => SPARK_LEVELS[((v - min) / (max - min) * top).round() as usize],
+        })
+        .collect()
+}
+
+/// Axis label: whole numbers as-is, others with at most two decimals
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{:.0}", value);
+    }
+    let formatted = format!("{:.2}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_series_from_metric_rows() {
+        let result = json!({"data": [{
+            "metric": "system.cpu.user",
+            "scope": "host:a",
+            "points": {"count": 3, "data": [
+                {"timestamp": "t1", "value": 1.0},
+                {"timestamp": "t2", "value": null},
+                {"timestamp": "t3", "value": 3.0},
+            ]},
+            "unit": {"name": "percent", "family": "percentage", "short_name": "%"},
+        }]});
+
+        assert_eq!(
+            series(&result),
+            vec![Series {
+                label: "system.cpu.user (host:a)".to_string(),
+                unit: Some("%".to_string()),
+                values: vec![Some(1.0), None, Some(3.0)],
+                start: Some("t1".to_string()),
+                end: Some("t3".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_series_from_timeseries_buckets() {
+        let result = json!({
+            "data": [
+                {"service": "web", "timestamp": "t1", "c0": 4},
+                {"service": "web", "timestamp": "t2", "c0": 6},
+                {"service": "api", "timestamp": "t1", "c0": 1},
+            ],
+            "meta": {"computes": {"c0": "count"}},
+        });
+
+        let lines = series(&result);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].label, "count (service=web)");
+        assert_eq!(lines[0].values, vec![Some(4.0), Some(6.0)]);
+        assert_eq!(lines[0].end.as_deref(), Some("t2"));
+        assert_eq!(lines[1].label, "count (service=api)");
+        assert_eq!(lines[1].values, vec![Some(1.0), None]);
+
+        // A bucket without one of the computes leaves a gap in that series only
+        let result = json!({
+            "data": [
+                {"timestamp": "t1", "c0": 1, "c1": 10},
+                {"timestamp": "t2", "c1": 20},
+                {"timestamp": "t3", "c0": 3, "c1": 30},
+            ],
+            "meta": {"computes": {"c0": "count", "c1": "avg(@duration)"}},
+        });
+        let lines = series(&result);
+        assert_eq!(lines[0].label, "count");
+        assert_eq!(lines[0].values, vec![Some(1.0), None, Some(3.0)]);
+        assert_eq!(lines[1].values, vec![Some(10.0), Some(20.0), Some(30.0)]);
+
+        assert!(series(&json!({"data": [{"id": 1}]})).is_empty());
+    }
+
+    #[test]
+    fn test_render_draws_axes_and_line() {
+        let line = Series {
+            label: "latency".to_string(),
+            unit: Some("ms".to_string()),
+            values: vec![Some(10.0), Some(20.0), None, Some(15.5)],
+            start: Some("t1".to_string()),
+            end: Some("t4".to_string()),
+        };
+
+        let chart = render(&line, 40);
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(lines[0], "latency");
+        assert!(lines[1].starts_with("20 ms ┤"));
+        assert!(lines[CHART_HEIGHT].starts_with("10 ms ┤"));
+        assert!(lines[CHART_HEIGHT + 1].trim_start().starts_with('└'));
+        assert!(lines[CHART_HEIGHT + 2].trim().starts_with("t1"));
+        assert!(lines[CHART_HEIGHT + 2].trim().ends_with("t4"));
+        assert!(lines.iter().all(|l| l.chars().count() <= 40));
+        // Top-left and bottom-left dots belong to the first and second points
+        assert!(lines[CHART_HEIGHT].contains(|c: char| c as u32 > 0x2800));
+        assert!(lines[1].contains(|c: char| c as u32 > 0x2800));
+
+        let empty = Series {
+            values: vec![None],
+            ..line
+        };
+        assert_eq!(render(&empty, 40), "latency\n  (no data)\n");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        let values: Vec<Option<f64>> = [0.0, 7.0, 3.5].iter().copied().map(Some).collect();
+        assert_eq!(sparkline(&values, 30), "▁█▅");
+        assert_eq!(sparkline(&[Some(1.0), None, Some(1.0)], 30), "▄ ▄");
+        assert_eq!(
+            sparkline(&[Some(0.0), Some(2.0), Some(4.0), Some(6.0)], 2),
+            "▁█"
+        );
+        assert_eq!(sparkline(&[], 30), "");
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(250.0), "250");
+        assert_eq!(format_number(12.5), "12.5");
+        assert_eq!(format_number(0.123456), "0.12");
+    }
+}
@@ -1,29 +1,111 @@
 use serde_json::{Value, json};
+use std::future::Future;
 use std::sync::Arc;
 
-use super::{Command, ConfigAction, DashboardsAction, LogsAction, MonitorsAction};
-use crate::config::Config;
+use super::output::PageStream;
+use super::{
+    AuthAction, Command, ConfigAction, DashboardsAction, LogsAction, MetricsAction, MonitorsAction,
+};
+use crate::config::{Config, ConfigOverrides, ConfigScope};
 use crate::datadog::DatadogClient;
 use crate::error::{DatadogError, Result};
 use crate::handlers;
+use crate::handlers::pagination::OffsetParams;
 
 pub async fn execute(
     command: &Command,
     client: Arc<DatadogClient>,
     config: &Config,
+    pages: &mut PageStream<'_>,
 ) -> Result<Value> {
     match command {
+        Command::Metrics {
+            action:
+                Some(MetricsAction::Query {
+                    queries,
+                    formulas,
+                    from,
+                    to,
+                    interval,
+                    summary,
+                    summary_only,
+                    compare,
+                }),
+            ..
+        } => {
+            let queries = queries
+                .iter()
+                .enumerate()
+                .map(|(i, spec)| {
+                    let (name, query) =
+                        handlers::metrics::MetricsHandler::parse_named_query(spec, i + 1)?;
+                    Ok(json!({"name": name, "query": query}))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let interval = interval
+                .as_deref()
+                .map(crate::utils::parse_duration)
+                .transpose()?;
+            let compare = compare
+                .as_deref()
+                .map(crate::utils::parse_duration)
+                .transpose()?;
+            let params = json!({
+                "queries": queries,
+                "formulas": formulas,
+                "from": from.as_deref().unwrap_or(config.time_range("metrics")),
+                "to": to,
+                "interval": interval,
+                "summary": summary,
+                "summary_only": summary_only,
+                "compare": compare,
+            });
+            handlers::metrics::MetricsHandler::formula_query(client, &params).await
+        }
+
+        Command::Metrics {
+            action:
+                Some(MetricsAction::Scalar {
+                    query,
+                    aggregator,
+                    from,
+                    to,
+                    assert,
+                }),
+            ..
+        } => {
+            let params = json!({
+                "query": query,
+                "aggregator": aggregator,
+                "from": from.as_deref().unwrap_or(config.time_range("metrics")),
+                "to": to,
+                "assert": assert,
+            });
+            handlers::metrics::MetricsHandler::scalar(client, &params).await
+        }
+
         Command::Metrics {
             query,
             from,
             to,
             max_points,
+            summary,
+            summary_only,
+            compare,
+            ..
         } => {
+            let compare = compare
+                .as_deref()
+                .map(crate::utils::parse_duration)
+                .transpose()?;
             let params = json!({
                 "query": query,
-                "from": from,
+                "from": from.as_deref().unwrap_or(config.time_range("metrics")),
                 "to": to,
                 "max_points": max_points,
+                "summary": summary,
+                "summary_only": summary_only,
+                "compare": compare,
             });
             handlers::metrics::MetricsHandler::query(client, &params).await
         }
@@ -35,25 +117,85 @@ pub async fn execute(
                 to,
                 limit,
                 cursor,
+                all,
+                max_results,
                 sort,
                 tag_filter,
             } => {
+                let paged = *all || max_results.is_some();
                 let params = json!({
                     "query": query,
-                    "from": from,
+                    "from": from.as_deref().unwrap_or(config.time_range("logs")),
                     "to": to,
-                    "limit": limit,
+                    "limit": limit.unwrap_or_else(|| page_limit(paged, config.limit("logs"))),
                     "cursor": cursor,
                     "sort": sort,
                     "tag_filter": tag_filter.as_ref().or(config.defaults.tag_filter.as_ref()),
+                    "attributes": pages.is_shaped(),
                 });
+                if paged {
+                    return stream_cursor(&params, *max_results, pages, |p| {
+                        let client = client.clone();
+                        async move { handlers::logs::LogsHandler::search(client, &p).await }
+                    })
+                    .await;
+                }
                 handlers::logs::LogsHandler::search(client, &params).await
             }
-            LogsAction::Aggregate { query, from, to } => {
+            LogsAction::Tail {
+                query,
+                from,
+                interval,
+                limit,
+                tag_filter,
+            } => {
                 let params = json!({
                     "query": query,
                     "from": from,
+                    "interval": interval,
+                    "limit": limit,
+                    "tag_filter": tag_filter.as_ref().or(config.defaults.tag_filter.as_ref()),
+                    "attributes": pages.is_shaped(),
+                });
+
+                pages.follow();
+                let tail = handlers::logs::LogsHandler::tail(
+                    client,
+                    &params,
+                    |logs| pages.write_page(&json!({ "data": logs })),
+                    |error, delay| {
+                        eprintln!(
+                            "Warning: {}; retrying in {}s",
+                            error,
+                            delay.as_secs_f64().ceil()
+                        )
+                    },
+                );
+                tokio::select! {
+                    result = tail => result?,
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+
+                // Everything went out through `pages`; `run` only closes the stream
+                Ok(Value::Null)
+            }
+            LogsAction::Aggregate {
+                query,
+                from,
+                to,
+                group_by,
+                compute,
+                timezone,
+                raw,
+            } => {
+                let params = json!({
+                    "query": query,
+                    "from": from.as_deref().unwrap_or(config.time_range("logs")),
                     "to": to,
+                    "group_by": log_group_by(group_by)?,
+                    "compute": log_computes(compute)?,
+                    "timezone": log_timezone(timezone.as_deref())?,
+                    "raw": raw,
                 });
                 handlers::logs::LogsHandler::aggregate(client, &params).await
             }
@@ -64,14 +206,22 @@ pub async fn execute(
                 interval,
                 aggregation,
                 metric,
+                group_by,
+                compute,
+                timezone,
+                raw,
             } => {
                 let params = json!({
                     "query": query,
-                    "from": from,
+                    "from": from.as_deref().unwrap_or(config.time_range("logs")),
                     "to": to,
                     "interval": interval,
                     "aggregation": aggregation,
                     "metric": metric,
+                    "group_by": log_group_by(group_by)?,
+                    "compute": log_computes(compute)?,
+                    "timezone": log_timezone(timezone.as_deref())?,
+                    "raw": raw,
                 });
                 handlers::logs::LogsHandler::timeseries(client, &params).await
             }
@@ -83,13 +233,26 @@ pub async fn execute(
                 monitor_tags,
                 page,
                 page_size,
+                all,
+                max_results,
             } => {
                 let params = json!({
                     "tags": tags,
                     "monitor_tags": monitor_tags,
                     "page": page,
-                    "page_size": page_size,
+                    "page_size": page_size.unwrap_or(config.page_size("monitors")),
                 });
+                if *all || max_results.is_some() {
+                    let style = OffsetParams::Page {
+                        page: "page",
+                        size: "page_size",
+                    };
+                    return stream_offsets(&params, style, *max_results, pages, |p| {
+                        let client = client.clone();
+                        async move { handlers::monitors::MonitorsHandler::list(client, &p).await }
+                    })
+                    .await;
+                }
                 handlers::monitors::MonitorsHandler::list(client, &params).await
             }
             MonitorsAction::Get { monitor_id } => {
@@ -106,7 +269,7 @@ pub async fn execute(
             tags,
         } => {
             let params = json!({
-                "from": from,
+                "from": from.as_deref().unwrap_or(config.time_range("events")),
                 "to": to,
                 "priority": priority,
                 "sources": sources,
@@ -122,17 +285,30 @@ pub async fn execute(
             sort_dir,
             start,
             count,
+            all,
+            max_results,
             tag_filter,
         } => {
             let params = json!({
                 "filter": filter,
-                "from": from,
+                "from": from.as_deref().unwrap_or(config.time_range("hosts")),
                 "sort_field": sort_field,
                 "sort_dir": sort_dir,
                 "start": start,
-                "count": count,
+                "count": count.unwrap_or(config.page_size("hosts")),
                 "tag_filter": tag_filter.as_ref().or(config.defaults.tag_filter.as_ref()),
             });
+            if *all || max_results.is_some() {
+                let style = OffsetParams::Start {
+                    start: "start",
+                    count: "count",
+                };
+                return stream_offsets(&params, style, *max_results, pages, |p| {
+                    let client = client.clone();
+                    async move { handlers::hosts::HostsHandler::list(client, &p).await }
+                })
+                .await;
+            }
             handlers::hosts::HostsHandler::list(client, &params).await
         }
 
@@ -140,15 +316,30 @@ pub async fn execute(
             DashboardsAction::List {
                 count,
                 start,
+                all,
+                max_results,
                 filter_shared,
                 filter_deleted,
             } => {
                 let params = json!({
-                    "count": count,
+                    "count": count.unwrap_or(config.page_size("dashboards")),
                     "start": start,
                     "filter_shared": filter_shared,
                     "filter_deleted": filter_deleted,
                 });
+                if *all || max_results.is_some() {
+                    let style = OffsetParams::Start {
+                        start: "start",
+                        count: "count",
+                    };
+                    return stream_offsets(&params, style, *max_results, pages, |p| {
+                        let client = client.clone();
+                        async move {
+                            handlers::dashboards::DashboardsHandler::list(client, &p).await
+                        }
+                    })
+                    .await;
+                }
                 handlers::dashboards::DashboardsHandler::list(client, &params).await
             }
             DashboardsAction::Get { dashboard_id } => {
@@ -163,20 +354,30 @@ pub async fn execute(
             to,
             limit,
             cursor,
+            all,
+            max_results,
             sort,
             tag_filter,
             full_stack_trace,
         } => {
+            let paged = *all || max_results.is_some();
             let params = json!({
                 "query": query,
-                "from": from,
+                "from": from.as_deref().unwrap_or(config.time_range("spans")),
                 "to": to,
-                "limit": limit,
+                "limit": limit.unwrap_or_else(|| page_limit(paged, config.limit("spans"))),
                 "cursor": cursor,
                 "sort": sort,
                 "tag_filter": tag_filter.as_ref().or(config.defaults.tag_filter.as_ref()),
                 "full_stack_trace": full_stack_trace,
             });
+            if paged {
+                return stream_cursor(&params, *max_results, pages, |p| {
+                    let client = client.clone();
+                    async move { handlers::spans::SpansHandler::list(client, &p).await }
+                })
+                .await;
+            }
             handlers::spans::SpansHandler::list(client, &params).await
         }
 
@@ -184,12 +385,25 @@ pub async fn execute(
             env,
             page_size,
             page,
+            all,
+            max_results,
         } => {
             let params = json!({
                 "env": env,
-                "page_size": page_size,
+                "page_size": page_size.unwrap_or(config.page_size("services")),
                 "page": page,
             });
+            if *all || max_results.is_some() {
+                let style = OffsetParams::Page {
+                    page: "page",
+                    size: "page_size",
+                };
+                return stream_offsets(&params, style, *max_results, pages, |p| {
+                    let client = client.clone();
+                    async move { handlers::services::ServicesHandler::list(client, &p).await }
+                })
+                .await;
+            }
             handlers::services::ServicesHandler::list(client, &params).await
         }
 
@@ -199,37 +413,62 @@ pub async fn execute(
             to,
             limit,
             cursor,
+            all,
+            max_results,
             sort,
             tag_filter,
             full_stack_trace,
         } => {
+            let paged = *all || max_results.is_some();
             let params = json!({
                 "query": query,
-                "from": from,
+                "from": from.as_deref().unwrap_or(config.time_range("rum")),
                 "to": to,
-                "limit": limit,
+                "limit": limit.unwrap_or_else(|| page_limit(paged, config.limit("rum"))),
                 "cursor": cursor,
                 "sort": sort,
                 "tag_filter": tag_filter.as_ref().or(config.defaults.tag_filter.as_ref()),
                 "full_stack_trace": full_stack_trace,
+                "attributes": pages.is_shaped(),
             });
+            if paged {
+                return stream_cursor(&params, *max_results, pages, |p| {
+                    let client = client.clone();
+                    async move { handlers::rum::RumHandler::search_events(client, &p).await }
+                })
+                .await;
+            }
             handlers::rum::RumHandler::search_events(client, &params).await
         }
 
+        Command::Config {
+            action: ConfigAction::Validate { scopes },
+        }
+        | Command::Auth {
+            action: AuthAction::Check { scopes },
+        } => {
+            let params = json!({
+                "site": config.site,
+                "api_url": config.base_url(),
+                "scopes": scopes,
+            });
+            handlers::auth::AuthHandler::check(client, &params).await
+        }
+
         Command::Config { .. } => {
             unreachable!("Config command is handled separately")
         }
     }
 }
 
-pub fn handle_config(action: &ConfigAction) -> Result<()> {
+pub fn handle_config(action: &ConfigAction, overrides: ConfigOverrides) -> Result<()> {
     match action {
         ConfigAction::Init => {
             let path = Config::init()?;
             println!("Created: {}", path.display());
         }
-        ConfigAction::Show => {
-            let output = Config::show()?;
+        ConfigAction::Show { sources } => {
+            let output = Config::show(overrides, *sources)?;
             println!("{}", output);
         }
         ConfigAction::Path => {
@@ -240,7 +479,121 @@ pub fn handle_config(action: &ConfigAction) -> Result<()> {
         ConfigAction::Edit => {
             Config::edit()?;
         }
+        ConfigAction::Set {
+            key,
+            value,
+            project,
+        } => {
+            let path = Config::set_value(key, value, scope(*project))?;
+            println!("Set {} in {}", key, path.display());
+        }
+        ConfigAction::Get {
+            key,
+            global,
+            project,
+        } => {
+            let scope = (*global || *project).then(|| scope(*project));
+            println!("{}", Config::get_value(key, scope)?);
+        }
+        ConfigAction::Unset { key, project } => {
+            let (path, removed) = Config::unset_value(key, scope(*project))?;
+            if removed {
+                println!("Unset {} in {}", key, path.display());
+            } else {
+                println!("{} was not set in {}", key, path.display());
+            }
+        }
+        ConfigAction::Validate { .. } => {
+            unreachable!("Config validate needs a client and is handled by execute")
+        }
     }
 
     Ok(())
 }
+
+/// Parse `--group-by` specs up front so a typo fails before any request is sent
+fn log_group_by(specs: &[String]) -> Result<Value> {
+    let group_by = specs
+        .iter()
+        .map(|spec| handlers::logs::LogsHandler::parse_group_by(spec))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(if group_by.is_empty() {
+        Value::Null
+    } else {
+        json!(group_by)
+    })
+}
+
+fn log_computes(specs: &[String]) -> Result<Value> {
+    let computes = specs
+        .iter()
+        .map(|spec| handlers::logs::LogsHandler::parse_compute(spec))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(if computes.is_empty() {
+        Value::Null
+    } else {
+        json!(computes)
+    })
+}
+
+fn log_timezone(timezone: Option<&str>) -> Result<Option<&str>> {
+    if let Some(tz) = timezone {
+        handlers::logs::LogsHandler::validate_timezone(tz)?;
+    }
+    Ok(timezone)
+}
+
+/// Page size for cursor commands: the largest page when following cursors, the configured limit otherwise
+fn page_limit(paged: bool, configured: i32) -> i32 {
+    if paged {
+        handlers::pagination::MAX_CURSOR_PAGE_LIMIT
+    } else {
+        configured
+    }
+}
+
+async fn stream_cursor<F, Fut>(
+    params: &Value,
+    max_results: Option<usize>,
+    pages: &mut PageStream<'_>,
+    fetch: F,
+) -> Result<Value>
+where
+    F: Fn(Value) -> Fut,
+    Fut: Future<Output = Result<Value>>,
+{
+    let pagination = handlers::pagination::follow_cursor(params, max_results, fetch, |page| {
+        pages.write_page(&page)
+    })
+    .await?;
+
+    Ok(json!({ "data": [], "pagination": pagination }))
+}
+
+async fn stream_offsets<F, Fut>(
+    params: &Value,
+    style: OffsetParams,
+    max_results: Option<usize>,
+    pages: &mut PageStream<'_>,
+    fetch: F,
+) -> Result<Value>
+where
+    F: Fn(Value) -> Fut,
+    Fut: Future<Output = Result<Value>> + Send + 'static,
+{
+    let pagination =
+        handlers::pagination::follow_offsets(params, style, max_results, fetch, |page| {
+            pages.write_page(&page)
+        })
+        .await?;
+
+    Ok(json!({ "data": [], "pagination": pagination }))
+}
+
+fn scope(project: bool) -> ConfigScope {
+    if project {
+        ConfigScope::Project
+    } else {
+        ConfigScope::Global
+    }
+}
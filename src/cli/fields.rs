@@ -0,0 +1,256 @@
+use serde_json::{Map, Value};
+
+use crate::error::{DatadogError, Result};
+
+/// Fields picked from each result row by `--fields`
+///
+/// Paths are dotted (`attributes.http.status_code`) and may index arrays with
+/// `[n]` or fan out over every element with `[*]`. Projected rows use the path
+/// as written for their keys, and table and CSV/TSV columns follow the order
+/// given in `names`.
+pub struct FieldSelector {
+    fields: Vec<(String, Vec<Segment>)>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl FieldSelector {
+    /// Parse a comma-separated list such as `id,timestamp,tags[*]`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let fields = spec
+            .split(',')
+            .map(str::trim)
+            .map(|field| Ok((field.to_string(), parse_path(field)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { fields })
+    }
+
+    /// The selected paths, in the order they were requested
+    pub fn names(&self) -> Vec<String> {
+        self.fields.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Project the rows under `data`, leaving `pagination` and `meta` untouched
+    pub fn apply(&self, response: &Value) -> Value {
+        let mut response = response.clone();
+        match response.get_mut("data") {
+            Some(Value::Array(rows)) => {
+                for row in rows.iter_mut() {
+                    *row = self.project(row);
+                }
+            }
+            Some(row @ Value::Object(_)) => *row = self.project(row),
+            _ => {}
+        }
+        response
+    }
+
+    fn project(&self, row: &Value) -> Value {
+        let projected: Map<String, Value> = self
+            .fields
+            .iter()
+            .map(|(name, path)| (name.clone(), select(row, path)))
+            .collect();
+        Value::Object(projected)
+    }
+}
+
+fn parse_path(field: &str) -> Result<Vec<Segment>> {
+    let invalid = |reason: &str| {
+        DatadogError::InvalidInput(format!("Invalid field '{}' in --fields: {}", field, reason))
+    };
+
+    if field.is_empty() {
+        return Err(invalid("empty field name"));
+    }
+
+    let mut segments = Vec::new();
+    for part in field.split('.') {
+        let (key, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+        match key {
+            "" if rest.is_empty() => return Err(invalid("empty path segment")),
+            "" => {}
+            "*" => segments.push(Segment::Wildcard),
+            key => segments.push(Segment::Key(key.to_string())),
+        }
+
+        while !rest.is_empty() {
+            let close = rest
+                .find(']')
+                .ok_or_else(|| invalid("missing closing ']'"))?;
+            let index = &rest[1..close];
+            segments.push(match index {
+                "*" => Segment::Wildcard,
+                n => Segment::Index(
+                    n.parse()
+                        .map_err(|_| invalid("array index must be a number or *"))?,
+                ),
+            });
+            rest = &rest[close + 1..];
+            if !rest.is_empty() && !rest.starts_with('[') {
+                return Err(invalid("unexpected text after ']'"));
+            }
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Follow `path` into `value`; a wildcard collects the rest of the path from
+/// every element and drops the ones where it is missing
+fn select(value: &Value, path: &[Segment]) -> Value {
+    let Some((segment, rest)) = path.split_first() else {
+        return value.clone();
+    };
+
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(obj)) => {
+            obj.get(key).map_or(Value::Null, |v| select(v, rest))
+        }
+        (Segment::Index(i), Value::Array(items)) => {
+            items.get(*i).map_or(Value::Null, |v| select(v, rest))
+        }
+        (Segment::Wildcard, Value::Array(items)) => collect(items.iter(), rest),
+        (Segment::Wildcard, Value::Object(obj)) => collect(obj.values(), rest),
+        _ => Value::Null,
+    }
+}
+
+fn collect<'a>(items: impl Iterator<Item = &'a Value>, rest: &[Segment]) -> Value {
+    Value::Array(
+        items
+            .map(|item| select(item, rest))
+            .filter(|v| !v.is_null())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_paths() {
+        assert_eq!(
+            parse_path("attributes.http.status_code").unwrap(),
+            vec![
+                Segment::Key("attributes".into()),
+                Segment::Key("http".into()),
+                Segment::Key("status_code".into()),
+            ]
+        );
+        assert_eq!(
+            parse_path("spans[*].tags[0]").unwrap(),
+            vec![
+                Segment::Key("spans".into()),
+                Segment::Wildcard,
+                Segment::Key("tags".into()),
+                Segment::Index(0),
+            ]
+        );
+
+        for bad in ["", "a..b", "tags[", "tags[x]", "tags[0]x", ".a"] {
+            assert!(FieldSelector::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_apply_projects_rows_in_order() {
+        let response = json!({
+            "data": [
+                {
+                    "id": "1",
+                    "timestamp": "t1",
+                    "attributes": {"http": {"status_code": 500}, "items": [{"id": "a"}, {"id": "b"}, {}]},
+                    "tags": ["env:prod", "team:x"],
+                },
+                {"id": "2", "tags": []},
+            ],
+            "pagination": {"has_next": false},
+        });
+
+        let selector = FieldSelector::parse(
+            "timestamp, id, attributes.http.status_code, attributes.items[*].id, tags[0]",
+        )
+        .unwrap();
+        let projected = selector.apply(&response);
+
+        let first = projected["data"][0].as_object().unwrap();
+        assert_eq!(first.len(), 5);
+        assert_eq!(
+            selector.names(),
+            vec![
+                "timestamp",
+                "id",
+                "attributes.http.status_code",
+                "attributes.items[*].id",
+                "tags[0]"
+            ]
+        );
+        assert_eq!(first["attributes.http.status_code"], 500);
+        assert_eq!(first["attributes.items[*].id"], json!(["a", "b"]));
+        assert_eq!(first["tags[0]"], "env:prod");
+
+        assert_eq!(projected["data"][1]["timestamp"], Value::Null);
+        assert_eq!(projected["pagination"], response["pagination"]);
+    }
+
+    #[test]
+    fn test_apply_projects_detail_object() {
+        let response =
+            json!({"data": {"id": 7, "name": "cpu", "options": {"thresholds": {"critical": 90}}}});
+        let selector = FieldSelector::parse("name,options.thresholds.critical").unwrap();
+
+        assert_eq!(
+            selector.apply(&response)["data"],
+            json!({"name": "cpu", "options.thresholds.critical": 90})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_selects_log_attributes() {
+        use crate::datadog::DatadogClient;
+        use crate::handlers::logs::LogsHandler;
+        use std::sync::Arc;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/logs/events/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{
+                    "id": "AAA",
+                    "attributes": {
+                        "timestamp": "2024-01-01T00:00:00Z",
+                        "message": "boom",
+                        "attributes": {"http": {"status_code": 503, "method": "GET"}},
+                    },
+                }],
+            })))
+            .mount(&server)
+            .await;
+
+        let client = DatadogClient::new("key".to_string(), "app".to_string(), None, 5, 0, None)
+            .unwrap()
+            .with_base_url(&server.uri());
+        let params =
+            json!({"query": "*", "from": "1704067200", "to": "1704070800", "attributes": true});
+        let page = LogsHandler::search(Arc::new(client), &params)
+            .await
+            .unwrap();
+
+        let selector = FieldSelector::parse("id,attributes.http.status_code").unwrap();
+        assert_eq!(
+            selector.apply(&page)["data"],
+            json!([{"id": "AAA", "attributes.http.status_code": 503}])
+        );
+    }
+}
@@ -1,25 +1,59 @@
+mod chart;
 mod commands;
+mod fields;
 mod output;
+mod query;
 
 use clap::{Parser, Subcommand};
 use std::sync::Arc;
 
-use crate::config::Config;
+use crate::config::{Config, ConfigOverrides};
 use crate::datadog::DatadogClient;
-use crate::error::Result;
+use crate::error::{DatadogError, Result};
 
 const TIME_HELP: &str =
     "Time format: 'now', '1 hour ago', '2024-01-01T00:00:00Z', or Unix timestamp";
+const FROM_HELP: &str = "Start time (default from config time_range). Time format: 'now', '1 hour ago', '2024-01-01T00:00:00Z', or Unix timestamp";
 const SORT_HELP: &str = "Sort order (use --sort=\"-timestamp\" for descending)";
+const SUMMARY_HELP: &str =
+    "Add per-series count, min, max, mean, last, sum, stddev and p50/p90/p95/p99";
+
+const SCOPES_HELP: &str =
+    "Also probe the read scope of every command (one extra API request per command)";
+
+const COMPARE_HELP: &str = "Compare with the same query shifted back, e.g. 1d, 1w, 6h";
 
 #[derive(Parser)]
 #[command(name = "datadog-cli")]
 #[command(version)]
 #[command(about = "High-performance Datadog CLI")]
 pub struct Cli {
-    #[arg(long, value_parser = ["json", "jsonl", "table"], help = "Output format (default from config)")]
+    #[arg(long, value_parser = ["json", "jsonl", "table", "chart", "csv", "tsv"], help = "Output format (default from config)")]
     pub format: Option<String>,
 
+    #[arg(
+        long,
+        default_value = output::DEFAULT_ARRAY_SEPARATOR,
+        help = "Separator for array values in csv/tsv cells"
+    )]
+    pub array_separator: String,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATHS",
+        help = "Comma-separated fields to keep, e.g. id,attributes.http.status_code,tags[*]"
+    )]
+    pub fields: Option<String>,
+
+    #[arg(
+        long = "query",
+        global = true,
+        value_name = "EXPR",
+        help = "jq-style filter applied to the result, e.g. '[.data[] | select(.status == \"error\") | .id]'"
+    )]
+    pub filter: Option<String>,
+
     #[arg(short = 'v', long, global = true)]
     pub verbose: bool,
 
@@ -32,24 +66,57 @@ pub struct Cli {
     #[arg(long, env = "DD_SITE", global = true)]
     pub site: Option<String>,
 
+    #[arg(
+        long,
+        env = "DD_API_URL",
+        global = true,
+        help = "API base URL, overrides the one derived from --site"
+    )]
+    pub api_url: Option<String>,
+
+    #[arg(
+        long,
+        env = "DD_PROFILE",
+        global = true,
+        help = "Named profile from the config file"
+    )]
+    pub profile: Option<String>,
+
     #[command(subcommand)]
     pub command: Command,
 }
 
 #[derive(Subcommand)]
 pub enum Command {
-    #[command(about = "Query time series metrics")]
+    #[command(
+        about = "Query time series metrics",
+        args_conflicts_with_subcommands = true,
+        subcommand_negates_reqs = true
+    )]
     Metrics {
-        query: String,
+        #[command(subcommand)]
+        action: Option<MetricsAction>,
 
-        #[arg(long, default_value = "1 hour ago", help = TIME_HELP)]
-        from: String,
+        #[arg(required = true, help = "v1 metric query, e.g. avg:system.cpu.user{*}")]
+        query: Option<String>,
+
+        #[arg(long, help = FROM_HELP)]
+        from: Option<String>,
 
         #[arg(long, default_value = "now", help = TIME_HELP)]
         to: String,
 
         #[arg(long, help = "Limit data points by auto-rollup")]
         max_points: Option<usize>,
+
+        #[arg(long, help = SUMMARY_HELP)]
+        summary: bool,
+
+        #[arg(long, help = "Print only the per-series summary, without points")]
+        summary_only: bool,
+
+        #[arg(long, value_name = "DURATION", help = COMPARE_HELP)]
+        compare: Option<String>,
     },
 
     #[command(about = "Log operations")]
@@ -66,8 +133,8 @@ pub enum Command {
 
     #[command(about = "Query events")]
     Events {
-        #[arg(long, default_value = "1 hour ago", help = TIME_HELP)]
-        from: String,
+        #[arg(long, help = FROM_HELP)]
+        from: Option<String>,
 
         #[arg(long, default_value = "now", help = TIME_HELP)]
         to: String,
@@ -87,8 +154,8 @@ pub enum Command {
         #[arg(long, help = "Filter hosts by name, alias, or tag")]
         filter: Option<String>,
 
-        #[arg(long, default_value = "1 hour ago", help = TIME_HELP)]
-        from: String,
+        #[arg(long, help = FROM_HELP)]
+        from: Option<String>,
 
         #[arg(long, help = "Sort field (e.g., cpu, iowait, load)")]
         sort_field: Option<String>,
@@ -99,8 +166,17 @@ pub enum Command {
         #[arg(long, default_value = "0", help = "Pagination offset")]
         start: i32,
 
-        #[arg(long, default_value = "100", help = "Results per page (max 1000)")]
-        count: i32,
+        #[arg(long, help = "Results per page, max 1000 (default from config)")]
+        count: Option<i32>,
+
+        #[arg(
+            long,
+            help = "Fetch every page (at most 10000 results unless --max-results)"
+        )]
+        all: bool,
+
+        #[arg(long, help = "Fetch pages until this many results")]
+        max_results: Option<usize>,
 
         #[arg(long, help = "Tag prefixes to include (default from config)")]
         tag_filter: Option<String>,
@@ -117,18 +193,24 @@ pub enum Command {
         #[arg(default_value = "*")]
         query: String,
 
-        #[arg(long, default_value = "1 hour ago", help = TIME_HELP)]
-        from: String,
+        #[arg(long, help = FROM_HELP)]
+        from: Option<String>,
 
         #[arg(long, default_value = "now", help = TIME_HELP)]
         to: String,
 
-        #[arg(long, default_value = "10")]
-        limit: i32,
+        #[arg(long, help = "Max results (default from config)")]
+        limit: Option<i32>,
 
         #[arg(long, help = "Pagination cursor from previous response")]
         cursor: Option<String>,
 
+        #[arg(long, help = "Follow cursors and fetch every page")]
+        all: bool,
+
+        #[arg(long, help = "Follow cursors until this many results")]
+        max_results: Option<usize>,
+
         #[arg(long, help = SORT_HELP)]
         sort: Option<String>,
 
@@ -144,11 +226,20 @@ pub enum Command {
         #[arg(long, help = "Filter by environment")]
         env: Option<String>,
 
-        #[arg(long, default_value = "100")]
-        page_size: i32,
+        #[arg(long, help = "Results per page (default from config)")]
+        page_size: Option<i32>,
 
         #[arg(long, default_value = "0")]
         page: i32,
+
+        #[arg(
+            long,
+            help = "Fetch every page (at most 10000 results unless --max-results)"
+        )]
+        all: bool,
+
+        #[arg(long, help = "Fetch pages until this many results")]
+        max_results: Option<usize>,
     },
 
     #[command(about = "Search RUM events")]
@@ -156,18 +247,24 @@ pub enum Command {
         #[arg(default_value = "*")]
         query: String,
 
-        #[arg(long, default_value = "1 hour ago", help = TIME_HELP)]
-        from: String,
+        #[arg(long, help = FROM_HELP)]
+        from: Option<String>,
 
         #[arg(long, default_value = "now", help = TIME_HELP)]
         to: String,
 
-        #[arg(long, default_value = "10")]
-        limit: i32,
+        #[arg(long, help = "Max results (default from config)")]
+        limit: Option<i32>,
 
         #[arg(long, help = "Pagination cursor from previous response")]
         cursor: Option<String>,
 
+        #[arg(long, help = "Follow cursors and fetch every page")]
+        all: bool,
+
+        #[arg(long, help = "Follow cursors until this many results")]
+        max_results: Option<usize>,
+
         #[arg(long, help = SORT_HELP)]
         sort: Option<String>,
 
@@ -183,6 +280,79 @@ pub enum Command {
         #[command(subcommand)]
         action: ConfigAction,
     },
+
+    #[command(about = "Authentication checks")]
+    Auth {
+        #[command(subcommand)]
+        action: AuthAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MetricsAction {
+    #[command(about = "Run named queries and formulas through the v2 timeseries API")]
+    Query {
+        #[arg(
+            short = 'q',
+            value_name = "NAME=QUERY",
+            required = true,
+            help = "Named query, repeatable, e.g. -q a=sum:requests{status:5xx}.as_count()"
+        )]
+        queries: Vec<String>,
+
+        #[arg(
+            long = "formula",
+            value_name = "EXPR",
+            help = "Formula over query names, repeatable, e.g. --formula \"100*a/b\""
+        )]
+        formulas: Vec<String>,
+
+        #[arg(long, help = FROM_HELP)]
+        from: Option<String>,
+
+        #[arg(long, default_value = "now", help = TIME_HELP)]
+        to: String,
+
+        #[arg(long, help = "Rollup interval, e.g. 60s, 5m, 1h")]
+        interval: Option<String>,
+
+        #[arg(long, help = SUMMARY_HELP)]
+        summary: bool,
+
+        #[arg(long, help = "Print only the per-series summary, without points")]
+        summary_only: bool,
+
+        #[arg(long, value_name = "DURATION", help = COMPARE_HELP)]
+        compare: Option<String>,
+    },
+
+    #[command(about = "Reduce a query to a single value per group")]
+    Scalar {
+        #[arg(help = "Metric query, e.g. p99:trace.http.request.duration{service:web}")]
+        query: String,
+
+        #[arg(
+            long,
+            default_value = "avg",
+            value_parser = ["avg", "max", "min", "sum", "last", "percentile"],
+            help = "How points are reduced; use percentile with pNN: distribution queries"
+        )]
+        aggregator: String,
+
+        #[arg(long, help = FROM_HELP)]
+        from: Option<String>,
+
+        #[arg(long, default_value = "now", help = TIME_HELP)]
+        to: String,
+
+        #[arg(
+            long,
+            value_name = "CONDITION",
+            allow_hyphen_values = true,
+            help = "Exit with status 9 unless every value satisfies it, e.g. \"< 250\""
+        )]
+        assert: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -192,18 +362,24 @@ pub enum LogsAction {
         #[arg(default_value = "*")]
         query: String,
 
-        #[arg(long, default_value = "1 hour ago", help = TIME_HELP)]
-        from: String,
+        #[arg(long, help = FROM_HELP)]
+        from: Option<String>,
 
         #[arg(long, default_value = "now", help = TIME_HELP)]
         to: String,
 
-        #[arg(long, default_value = "10")]
-        limit: i32,
+        #[arg(long, help = "Max results (default from config)")]
+        limit: Option<i32>,
 
         #[arg(long, help = "Pagination cursor from previous response")]
         cursor: Option<String>,
 
+        #[arg(long, help = "Follow cursors and fetch every page")]
+        all: bool,
+
+        #[arg(long, help = "Follow cursors until this many results")]
+        max_results: Option<usize>,
+
         #[arg(long, help = SORT_HELP)]
         sort: Option<String>,
 
@@ -211,16 +387,60 @@ pub enum LogsAction {
         tag_filter: Option<String>,
     },
 
+    #[command(about = "Follow new logs as they arrive (Ctrl-C to stop)")]
+    Tail {
+        #[arg(default_value = "*")]
+        query: String,
+
+        #[arg(long, default_value = "1 minute ago", help = TIME_HELP)]
+        from: String,
+
+        #[arg(long, default_value = "5", help = "Seconds between polls")]
+        interval: u64,
+
+        #[arg(long, default_value = "1000", help = "Max logs fetched per poll")]
+        limit: i32,
+
+        #[arg(long, help = "Tag prefixes to include (default from config)")]
+        tag_filter: Option<String>,
+    },
+
     #[command(about = "Aggregate logs into buckets")]
     Aggregate {
         #[arg(default_value = "*")]
         query: String,
 
-        #[arg(long, default_value = "1 hour ago", help = TIME_HELP)]
-        from: String,
+        #[arg(long, help = FROM_HELP)]
+        from: Option<String>,
 
         #[arg(long, default_value = "now", help = TIME_HELP)]
         to: String,
+
+        #[arg(
+            long = "group-by",
+            value_name = "FACET[:LIMIT]",
+            help = "Group by a facet, optionally keeping the top N groups (repeatable), e.g. service:10"
+        )]
+        group_by: Vec<String>,
+
+        #[arg(
+            long,
+            value_name = "AGG[:METRIC]",
+            help = "Value to compute per group (repeatable), e.g. count, avg:@duration, pc95:@duration"
+        )]
+        compute: Vec<String>,
+
+        #[arg(
+            long,
+            help = "Timezone for bucket boundaries (e.g., UTC, UTC+9, Asia/Seoul)"
+        )]
+        timezone: Option<String>,
+
+        #[arg(
+            long,
+            help = "Return Datadog's raw buckets instead of one row per group"
+        )]
+        raw: bool,
     },
 
     #[command(about = "Generate log timeseries")]
@@ -228,8 +448,8 @@ pub enum LogsAction {
         #[arg(default_value = "*")]
         query: String,
 
-        #[arg(long, default_value = "1 hour ago", help = TIME_HELP)]
-        from: String,
+        #[arg(long, help = FROM_HELP)]
+        from: Option<String>,
 
         #[arg(long, default_value = "now", help = TIME_HELP)]
         to: String,
@@ -250,6 +470,33 @@ pub enum LogsAction {
 
         #[arg(long, help = "Metric field for aggregation")]
         metric: Option<String>,
+
+        #[arg(
+            long = "group-by",
+            value_name = "FACET[:LIMIT]",
+            help = "Group by a facet, optionally keeping the top N groups (repeatable), e.g. service:10"
+        )]
+        group_by: Vec<String>,
+
+        #[arg(
+            long,
+            value_name = "AGG[:METRIC]",
+            conflicts_with_all = ["aggregation", "metric"],
+            help = "Value to compute per group (repeatable), e.g. count, avg:@duration, pc95:@duration"
+        )]
+        compute: Vec<String>,
+
+        #[arg(
+            long,
+            help = "Timezone for bucket boundaries (e.g., UTC, UTC+9, Asia/Seoul)"
+        )]
+        timezone: Option<String>,
+
+        #[arg(
+            long,
+            help = "Return Datadog's raw buckets instead of one row per group"
+        )]
+        raw: bool,
     },
 }
 
@@ -266,8 +513,17 @@ pub enum MonitorsAction {
         #[arg(long, default_value = "0", help = "Page number")]
         page: i32,
 
-        #[arg(long, default_value = "100", help = "Results per page")]
-        page_size: i32,
+        #[arg(long, help = "Results per page (default from config)")]
+        page_size: Option<i32>,
+
+        #[arg(
+            long,
+            help = "Fetch every page (at most 10000 results unless --max-results)"
+        )]
+        all: bool,
+
+        #[arg(long, help = "Fetch pages until this many results")]
+        max_results: Option<usize>,
     },
 
     #[command(about = "Get monitor details")]
@@ -278,12 +534,21 @@ pub enum MonitorsAction {
 pub enum DashboardsAction {
     #[command(about = "List dashboards")]
     List {
-        #[arg(long, default_value = "100", help = "Results per page")]
-        count: i32,
+        #[arg(long, help = "Results per page (default from config)")]
+        count: Option<i32>,
 
         #[arg(long, default_value = "0", help = "Pagination offset")]
         start: i32,
 
+        #[arg(
+            long,
+            help = "Fetch every page (at most 10000 results unless --max-results)"
+        )]
+        all: bool,
+
+        #[arg(long, help = "Fetch pages until this many results")]
+        max_results: Option<usize>,
+
         #[arg(long, help = "Include shared dashboards only")]
         filter_shared: bool,
 
@@ -301,37 +566,162 @@ pub enum ConfigAction {
     Init,
 
     #[command(about = "Show current config")]
-    Show,
+    Show {
+        #[arg(long, help = "Show which file, env var or flag set each value")]
+        sources: bool,
+    },
 
     #[command(about = "Show config file path")]
     Path,
 
     #[command(about = "Edit config file")]
     Edit,
+
+    #[command(about = "Check API/App keys and org against the API")]
+    Validate {
+        #[arg(long, help = SCOPES_HELP)]
+        scopes: bool,
+    },
+
+    #[command(about = "Set a config value (e.g. defaults.format table)")]
+    Set {
+        #[arg(help = "Dotted key, e.g. network.timeout_secs or profiles.staging.site")]
+        key: String,
+
+        value: String,
+
+        #[arg(
+            long,
+            help = "Write the project .datadog.toml instead of the global file"
+        )]
+        project: bool,
+    },
+
+    #[command(about = "Print a config value (merged from all config files by default)")]
+    Get {
+        key: String,
+
+        #[arg(long, conflicts_with = "project", help = "Read only the global file")]
+        global: bool,
+
+        #[arg(long, help = "Read only the project .datadog.toml")]
+        project: bool,
+    },
+
+    #[command(about = "Remove a config value")]
+    Unset {
+        key: String,
+
+        #[arg(
+            long,
+            help = "Edit the project .datadog.toml instead of the global file"
+        )]
+        project: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AuthAction {
+    #[command(about = "Check API/App keys and org (same as config validate)")]
+    Check {
+        #[arg(long, help = SCOPES_HELP)]
+        scopes: bool,
+    },
+}
+
+impl Command {
+    fn is_auth_check(&self) -> bool {
+        matches!(
+            self,
+            Command::Config {
+                action: ConfigAction::Validate { .. }
+            } | Command::Auth {
+                action: AuthAction::Check { .. }
+            }
+        )
+    }
+}
+
+impl Cli {
+    fn config_overrides(&self) -> ConfigOverrides {
+        ConfigOverrides {
+            api_key: self.api_key.clone(),
+            app_key: self.app_key.clone(),
+            site: self.site.clone(),
+            api_url: self.api_url.clone(),
+            profile: self.profile.clone(),
+        }
+    }
+}
+
+pub fn report_error(error: &DatadogError, cli_format: Option<&str>) {
+    let format_str = match cli_format {
+        Some(f) => f.to_string(),
+        None => Config::load_defaults_only().defaults.format,
+    };
+    let format = output::Format::from_str(&format_str).ok();
+    output::print_error(error, format.as_ref());
 }
 
 pub async fn run(cli: Cli) -> Result<()> {
-    if let Command::Config { ref action } = cli.command {
-        return commands::handle_config(action);
+    if let Command::Config { ref action } = cli.command
+        && !cli.command.is_auth_check()
+    {
+        return commands::handle_config(action, cli.config_overrides());
     }
 
-    let config = Config::load(cli.api_key, cli.app_key, cli.site)?;
+    let config = Config::load(cli.config_overrides())?;
 
-    let client = Arc::new(DatadogClient::new(
-        config.api_key().to_string(),
-        config.app_key().to_string(),
-        Some(config.site.clone()),
-        config.network.timeout_secs,
-        config.network.max_retries,
-        config.defaults.tag_filter.clone(),
-    )?);
+    let client = Arc::new(
+        DatadogClient::new(
+            config.api_key().to_string(),
+            config.app_key().to_string(),
+            Some(config.site.clone()),
+            config.network.timeout_secs,
+            config.network.max_retries,
+            config.defaults.tag_filter.clone(),
+        )?
+        .with_base_url(&config.base_url()),
+    );
 
     let format_str = cli.format.as_deref().unwrap_or(&config.defaults.format);
-    let format =
-        output::Format::from_str(format_str).map_err(crate::error::DatadogError::InvalidInput)?;
+    let format = output::Format::from_str(format_str)
+        .map_err(DatadogError::InvalidInput)?
+        .with_array_separator(&cli.array_separator);
+
+    let fields = cli
+        .fields
+        .as_deref()
+        .map(fields::FieldSelector::parse)
+        .transpose()?;
+
+    let filter = cli
+        .filter
+        .as_deref()
+        .map(query::Filter::parse)
+        .transpose()?;
+
+    let mut pages = output::PageStream::new(&format, fields.as_ref(), filter.as_ref());
+    let result = commands::execute(&cli.command, client, &config, &mut pages).await?;
+    if pages.is_paged() {
+        pages.finish(&result["pagination"])?;
+    } else {
+        let shaped = output::shape(&result, fields.as_ref(), filter.as_ref())?;
+        let columns = fields.as_ref().map(fields::FieldSelector::names);
+        output::print(&shaped, &format, &columns.unwrap_or_default())?;
+    }
 
-    let result = commands::execute(&cli.command, client, &config).await?;
-    output::print(&result, &format)?;
+    if cli.command.is_auth_check() {
+        crate::handlers::auth::AuthHandler::ensure_valid(&result)?;
+    }
+
+    if let Command::Metrics {
+        action: Some(MetricsAction::Scalar { .. }),
+        ..
+    } = cli.command
+    {
+        crate::handlers::metrics::MetricsHandler::ensure_assertion(&result)?;
+    }
 
     Ok(())
 }
@@ -1,29 +1,277 @@
-use comfy_table::{Table, presets::UTF8_FULL};
-use serde_json::Value;
+use comfy_table::{ContentArrangement, Row, Table, presets::UTF8_FULL};
+use serde_json::{Value, json};
 use std::io::{self, Write};
 
+use super::chart;
+use super::fields::FieldSelector;
+use super::query::Filter;
+use crate::error::{DatadogError, Result};
+
+/// Longest array or object rendered inline in a table cell; larger ones are summarized
+const INLINE_CELL_MAX_CHARS: usize = 60;
+
+/// Joins array elements inside a single CSV/TSV cell
+pub const DEFAULT_ARRAY_SEPARATOR: &str = ";";
+
 pub enum Format {
     Json,
     JsonLines,
     Table,
+    Chart,
+    Csv { array_separator: String },
+    Tsv { array_separator: String },
 }
 
 impl Format {
-    pub fn from_str(s: &str) -> Result<Self, String> {
+    pub fn from_str(s: &str) -> std::result::Result<Self, String> {
+        let array_separator = DEFAULT_ARRAY_SEPARATOR.to_string();
         match s.to_lowercase().as_str() {
             "json" => Ok(Format::Json),
             "jsonl" | "jsonlines" => Ok(Format::JsonLines),
             "table" => Ok(Format::Table),
+            "chart" => Ok(Format::Chart),
+            "csv" => Ok(Format::Csv { array_separator }),
+            "tsv" => Ok(Format::Tsv { array_separator }),
             _ => Err(format!("Invalid format: {}", s)),
         }
     }
+
+    /// Use `separator` to join arrays in CSV/TSV cells; other formats are unchanged
+    pub fn with_array_separator(self, separator: &str) -> Self {
+        match self {
+            Format::Csv { .. } => Format::Csv {
+                array_separator: separator.to_string(),
+            },
+            Format::Tsv { .. } => Format::Tsv {
+                array_separator: separator.to_string(),
+            },
+            other => other,
+        }
+    }
+
+    /// Field delimiter and array separator for the delimited formats
+    fn delimited(&self) -> Option<(char, &str)> {
+        match self {
+            Format::Csv { array_separator } => Some((',', array_separator)),
+            Format::Tsv { array_separator } => Some(('\t', array_separator)),
+            _ => None,
+        }
+    }
 }
 
-pub fn print(data: &Value, format: &Format) -> io::Result<()> {
+/// Print a result; table, CSV and TSV columns named in `columns` come first, in
+/// that order (the `--fields` selection), followed by any others
+pub fn print(data: &Value, format: &Format, columns: &[String]) -> io::Result<()> {
     match format {
         Format::Json => print_json(data),
         Format::JsonLines => print_jsonlines(data),
-        Format::Table => print_table(data),
+        Format::Table => print_table(data, columns),
+        Format::Chart => print_chart(data, columns),
+        Format::Csv { .. } | Format::Tsv { .. } => {
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            write_delimited(&mut handle, &rows(data), format, columns)
+        }
+    }
+}
+
+/// Writes paged results as they arrive
+///
+/// JSON Lines rows and the JSON `data` array are streamed page by page; tables
+/// need every row to size their columns and CSV/TSV need the union of every
+/// row's columns, so they are printed once at the end. In follow mode table
+/// rows are printed as plain lines and CSV/TSV keep the first page's columns.
+///
+/// A `--query` filter needs the whole result, so every page is buffered and
+/// filtered once at the end; in follow mode each page is filtered on its own.
+pub struct PageStream<'a> {
+    format: &'a Format,
+    fields: Option<&'a FieldSelector>,
+    filter: Option<&'a Filter>,
+    follow: bool,
+    paged: bool,
+    rows: Vec<Value>,
+    columns: Vec<String>,
+    written: usize,
+}
+
+impl<'a> PageStream<'a> {
+    pub fn new(
+        format: &'a Format,
+        fields: Option<&'a FieldSelector>,
+        filter: Option<&'a Filter>,
+    ) -> Self {
+        Self {
+            format,
+            fields,
+            filter,
+            follow: false,
+            paged: false,
+            rows: Vec::new(),
+            columns: Vec::new(),
+            written: 0,
+        }
+    }
+
+    /// Stream rows that never end, such as `logs tail`
+    pub fn follow(&mut self) {
+        self.follow = true;
+    }
+
+    /// Whether `--fields` or `--query` may select fields beyond the default row
+    pub fn is_shaped(&self) -> bool {
+        self.fields.is_some() || self.filter.is_some()
+    }
+
+    /// Whether the result went out page by page; a follow stream never falls back
+    /// to printing the whole result, even when interrupted before its first page
+    pub fn is_paged(&self) -> bool {
+        self.paged || self.follow
+    }
+
+    fn column_order(&self) -> Vec<String> {
+        self.fields.map(FieldSelector::names).unwrap_or_default()
+    }
+
+    pub fn write_page(&mut self, page: &Value) -> Result<()> {
+        let projected = self.fields.map(|fields| fields.apply(page));
+        let page = projected.as_ref().unwrap_or(page);
+
+        if let Some(filter) = self.filter {
+            self.paged = true;
+            if self.follow {
+                print(&filter.apply(page)?, self.format, &self.column_order())?;
+            } else if let Some(items) = page["data"].as_array() {
+                self.rows.extend(items.iter().cloned());
+            }
+            return Ok(());
+        }
+
+        let items = page["data"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+
+        match self.format {
+            Format::Json => {
+                if !self.paged {
+                    write!(handle, "{{\n  \"data\": [")?;
+                }
+                for item in items {
+                    let separator = if self.written == 0 { "" } else { "," };
+                    let pretty = serde_json::to_string_pretty(item)?.replace('\n', "\n    ");
+                    write!(handle, "{}\n    {}", separator, pretty)?;
+                    self.written += 1;
+                }
+            }
+            Format::JsonLines => {
+                for item in items {
+                    serde_json::to_writer(&mut handle, item)?;
+                    writeln!(handle)?;
+                }
+            }
+            Format::Table | Format::Chart if self.follow => {
+                let order = self.column_order();
+                for item in items {
+                    let line: Vec<String> = item
+                        .as_object()
+                        .map(|obj| {
+                            order_columns(obj.keys().collect(), &order)
+                                .into_iter()
+                                .map(|key| format_value(obj.get(key)))
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    writeln!(handle, "{}", line.join("  "))?;
+                }
+            }
+            Format::Csv { .. } | Format::Tsv { .. } if self.follow => {
+                let records: Vec<_> = items
+                    .iter()
+                    .map(|item| flatten_record(item, self.format))
+                    .collect();
+                if self.columns.is_empty() {
+                    self.columns = order_columns(union_columns(&records), &self.column_order());
+                    if !self.columns.is_empty() {
+                        write_record(&mut handle, &self.columns, self.format)?;
+                    }
+                }
+                for record in &records {
+                    let cells = record_cells(record, &self.columns);
+                    write_record(&mut handle, &cells, self.format)?;
+                }
+            }
+            Format::Table | Format::Chart | Format::Csv { .. } | Format::Tsv { .. } => {
+                self.rows.extend(items.iter().cloned())
+            }
+        }
+
+        self.paged = true;
+        Ok(handle.flush()?)
+    }
+
+    pub fn finish(self, pagination: &Value) -> Result<()> {
+        if let Some(filter) = self.filter {
+            if self.follow {
+                return Ok(());
+            }
+            let mut result = json!({ "data": self.rows });
+            if !pagination.is_null() {
+                result["pagination"] = pagination.clone();
+            }
+            return Ok(print(
+                &filter.apply(&result)?,
+                self.format,
+                &self.column_order(),
+            )?);
+        }
+
+        match self.format {
+            Format::Json => {
+                let stdout = io::stdout();
+                let mut handle = stdout.lock();
+                if !self.paged {
+                    write!(handle, "{{\n  \"data\": [")?;
+                }
+                let close = if self.written == 0 { "]" } else { "\n  ]" };
+                if pagination.is_null() {
+                    writeln!(handle, "{}\n}}", close)?;
+                    return Ok(());
+                }
+                let pretty = serde_json::to_string_pretty(pagination)?.replace('\n', "\n  ");
+                writeln!(handle, "{},\n  \"pagination\": {}\n}}", close, pretty)?;
+            }
+            Format::JsonLines => {}
+            Format::Table | Format::Chart | Format::Csv { .. } | Format::Tsv { .. }
+                if self.follow => {}
+            Format::Table | Format::Chart | Format::Csv { .. } | Format::Tsv { .. } => print(
+                &json!({ "data": self.rows, "pagination": pagination }),
+                self.format,
+                &self.column_order(),
+            )?,
+        }
+        Ok(())
+    }
+}
+
+pub fn print_error(error: &DatadogError, format: Option<&Format>) {
+    let kind = error.kind();
+
+    match format {
+        Some(Format::Json) | Some(Format::JsonLines) => {
+            let payload = json!({
+                "kind": kind.as_str(),
+                "message": error.to_string(),
+                "status": error.status(),
+                "hint": kind.hint(),
+            });
+            eprintln!("{}", payload);
+        }
+        _ => {
+            eprintln!("Error: {}", error);
+            if let Some(hint) = kind.hint() {
+                eprintln!("Hint: {}", hint);
+            }
+        }
     }
 }
 
@@ -57,40 +305,338 @@ fn print_jsonlines(data: &Value) -> io::Result<()> {
     Ok(())
 }
 
-fn print_table(data: &Value) -> io::Result<()> {
-    let items = if let Some(data_array) = data.get("data").and_then(|d| d.as_array()) {
-        data_array
-    } else if let Some(array) = data.as_array() {
-        array
-    } else {
-        eprintln!("Error: Data is not in array format for table output");
-        return Ok(());
-    };
+fn print_table(data: &Value, columns: &[String]) -> io::Result<()> {
+    match data.get("data").unwrap_or(data) {
+        Value::Array(items) if items.is_empty() => println!("No data"),
+        Value::Array(items) => {
+            println!("{}", list_table(items, columns));
+            if let Some(footer) = pagination_footer(data.get("pagination"), items.len()) {
+                println!("{footer}");
+            }
+        }
+        Value::Object(obj) => print!("{}", detail_tables(None, obj, columns)),
+        scalar => println!("{}", format_value(Some(scalar))),
+    }
+    Ok(())
+}
 
-    if items.is_empty() {
-        println!("No data");
-        return Ok(());
+/// One braille chart per series; results without series fall back to a table
+fn print_chart(data: &Value, columns: &[String]) -> io::Result<()> {
+    let series = chart::series(data);
+    if series.is_empty() {
+        return print_table(data, columns);
     }
 
+    let width = Table::new()
+        .width()
+        .map(usize::from)
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(80);
+    let charts: Vec<String> = series.iter().map(|s| chart::render(s, width)).collect();
+    print!("{}", charts.join("\n"));
+    Ok(())
+}
+
+fn list_table(items: &[Value], columns: &[String]) -> Table {
+    let rows: Vec<_> = items.iter().map(spark_row).collect();
+    let mut headers: Vec<&String> = Vec::new();
+    for row in &rows {
+        for key in row.keys() {
+            if !headers.contains(&key) {
+                headers.push(key);
+            }
+        }
+    }
+    let headers = order_columns(headers, columns);
+
+    // Rows are kept to one line; cells are cut with an ellipsis to fit the terminal
     let mut table = Table::new();
-    table.load_preset(UTF8_FULL);
+    table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_truncation_indicator("…")
+        .set_header(&headers);
 
-    if let Some(first) = items[0].as_object() {
-        let headers: Vec<_> = first.keys().collect();
-        table.set_header(&headers);
+    for row in &rows {
+        let cells: Vec<String> = headers.iter().map(|k| format_value(row.get(*k))).collect();
+        let mut row = Row::from(cells);
+        row.max_height(1);
+        table.add_row(row);
+    }
+    table
+}
 
-        for item in items {
-            if let Some(obj) = item.as_object() {
-                let row: Vec<_> = headers.iter().map(|k| format_value(obj.get(*k))).collect();
-                table.add_row(row);
+/// Render a single object as a key/value table
+///
+/// Nested objects follow as titled sections (`options`, `options.thresholds`)
+/// and arrays of objects, such as widgets, as list tables.
+fn detail_tables(
+    title: Option<&str>,
+    obj: &serde_json::Map<String, Value>,
+    columns: &[String],
+) -> String {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic);
+
+    let mut sections = Vec::new();
+    for key in order_columns(obj.keys().collect(), columns) {
+        let value = &obj[key];
+        let path = title.map_or_else(|| key.clone(), |t| format!("{}.{}", t, key));
+        match value {
+            Value::Object(nested) if !nested.is_empty() => sections.push((path, value)),
+            Value::Array(items) if items.iter().any(Value::is_object) => {
+                sections.push((path, value))
+            }
+            _ => {
+                table.add_row(vec![key.clone(), format_value(Some(value))]);
             }
         }
     }
 
-    println!("{table}");
+    let mut out = String::new();
+    if let Some(title) = title {
+        out.push_str(&format!("\n{}\n", title));
+    }
+    if table.row_count() > 0 {
+        out.push_str(&format!("{}\n", table));
+    }
+
+    for (path, value) in sections {
+        match value {
+            Value::Object(nested) => out.push_str(&detail_tables(Some(&path), nested, &[])),
+            Value::Array(items) => {
+                out.push_str(&format!(
+                    "\n{} ({})\n{}\n",
+                    path,
+                    items.len(),
+                    list_table(items, &[])
+                ));
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// A table row with nested objects flattened one level into dotted columns
+fn table_row(item: &Value) -> serde_json::Map<String, Value> {
+    let mut row = serde_json::Map::new();
+    match item {
+        Value::Object(obj) => {
+            for (key, value) in obj {
+                match value {
+                    Value::Object(nested) if !nested.is_empty() => {
+                        for (sub, sub_value) in nested {
+                            row.insert(format!("{}.{}", key, sub), sub_value.clone());
+                        }
+                    }
+                    _ => {
+                        row.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        other => {
+            row.insert("value".to_string(), other.clone());
+        }
+    }
+    row
+}
+
+/// Table row of a metric series, with its points drawn as a `spark` column
+fn spark_row(item: &Value) -> serde_json::Map<String, Value> {
+    let row = table_row(item);
+    let Some(spark) = chart::spark(item) else {
+        return row;
+    };
+
+    row.into_iter()
+        .map(|(key, value)| match key.as_str() {
+            "points.data" => ("spark".to_string(), json!(spark)),
+            _ => (key, value),
+        })
+        .collect()
+}
+
+/// Summarize `pagination` under a table, e.g. `25 rows · page 2 · next cursor: abc`
+fn pagination_footer(pagination: Option<&Value>, rows: usize) -> Option<String> {
+    let pagination = pagination?.as_object()?;
+    let mut parts = vec![format!("{} rows", rows)];
+
+    if let Some(page) = pagination.get("page").and_then(Value::as_u64) {
+        parts.push(format!("page {}", page + 1));
+    }
+    if let Some(cursor) = pagination.get("next_cursor").and_then(Value::as_str) {
+        parts.push(format!("next cursor: {}", cursor));
+    } else if let Some(offset) = pagination.get("next_offset").and_then(Value::as_u64)
+        && pagination.get("has_next") == Some(&Value::Bool(true))
+    {
+        parts.push(format!("more from offset {}", offset));
+    } else if let Some(page) = pagination.get("next_page").and_then(Value::as_u64)
+        && pagination.get("has_next") == Some(&Value::Bool(true))
+    {
+        parts.push(format!("more from --page {}", page));
+    } else if pagination.get("has_next") == Some(&Value::Bool(true)) {
+        parts.push("more available".to_string());
+    }
+
+    Some(parts.join(" · "))
+}
+
+/// Apply `--fields` then `--query` to a complete result
+pub fn shape(
+    result: &Value,
+    fields: Option<&FieldSelector>,
+    filter: Option<&Filter>,
+) -> Result<Value> {
+    let projected = fields.map(|fields| fields.apply(result));
+    let result = projected.as_ref().unwrap_or(result);
+    match filter {
+        Some(filter) => filter.apply(result),
+        None => Ok(result.clone()),
+    }
+}
+
+/// Rows of a response: its `data` array, a single detail object, or a bare array
+fn rows(data: &Value) -> Vec<Value> {
+    match data.get("data").unwrap_or(data) {
+        Value::Array(items) => items.clone(),
+        Value::Null => Vec::new(),
+        item => vec![item.clone()],
+    }
+}
+
+/// Flatten a row into cells keyed by dotted path, e.g. `attributes.http.status_code`
+fn flatten_record(item: &Value, format: &Format) -> Vec<(String, String)> {
+    let separator = format
+        .delimited()
+        .map_or(DEFAULT_ARRAY_SEPARATOR, |(_, s)| s);
+    let mut cells = Vec::new();
+
+    match item {
+        Value::Object(_) => flatten_into(&mut cells, String::new(), item, separator),
+        other => cells.push(("value".to_string(), cell_text(other, separator))),
+    }
+    cells
+}
+
+fn flatten_into(cells: &mut Vec<(String, String)>, prefix: String, value: &Value, separator: &str) {
+    match value {
+        Value::Object(obj) if !obj.is_empty() => {
+            for (key, child) in obj {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_into(cells, path, child, separator);
+            }
+        }
+        other => cells.push((prefix, cell_text(other, separator))),
+    }
+}
+
+fn cell_text(value: &Value, separator: &str) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Value::String(s) => s.clone(),
+                Value::Null => String::new(),
+                other => other.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(separator),
+        other => other.to_string(),
+    }
+}
+
+/// Move the `preferred` columns to the front, in that order; the rest keep theirs
+fn order_columns<T: AsRef<str>>(mut columns: Vec<T>, preferred: &[String]) -> Vec<T> {
+    columns.sort_by_key(|column| {
+        preferred
+            .iter()
+            .position(|p| p == column.as_ref())
+            .unwrap_or(usize::MAX)
+    });
+    columns
+}
+
+/// Every column that appears in any record, in first-seen order
+fn union_columns(records: &[Vec<(String, String)>]) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+    for record in records {
+        for (key, _) in record {
+            if !columns.contains(key) {
+                columns.push(key.clone());
+            }
+        }
+    }
+    columns
+}
+
+/// A record's cells in column order, empty where it lacks a column
+fn record_cells(record: &[(String, String)], columns: &[String]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|column| {
+            record
+                .iter()
+                .find(|(key, _)| key == column)
+                .map(|(_, cell)| cell.clone())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn write_delimited(
+    out: &mut impl Write,
+    items: &[Value],
+    format: &Format,
+    order: &[String],
+) -> io::Result<()> {
+    let records: Vec<_> = items
+        .iter()
+        .map(|item| flatten_record(item, format))
+        .collect();
+    let columns = order_columns(union_columns(&records), order);
+    if columns.is_empty() {
+        return Ok(());
+    }
+
+    write_record(out, &columns, format)?;
+    for record in &records {
+        write_record(out, &record_cells(record, &columns), format)?;
+    }
     Ok(())
 }
 
+/// Write one line, quoting fields RFC 4180 style when they hold the delimiter,
+/// quotes, line breaks or surrounding whitespace
+fn write_record(out: &mut impl Write, fields: &[String], format: &Format) -> io::Result<()> {
+    let (delimiter, _) = format.delimited().unwrap_or((',', DEFAULT_ARRAY_SEPARATOR));
+
+    let line: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            let needs_quotes = field.contains([delimiter, '"', '\n', '\r'])
+                || field.starts_with(char::is_whitespace)
+                || field.ends_with(char::is_whitespace);
+            if needs_quotes {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+
+    write!(out, "{}\r\n", line.join(&delimiter.to_string()))
+}
+
 fn format_value(value: Option<&Value>) -> String {
     match value {
         None => "-".to_string(),
@@ -98,13 +644,196 @@ fn format_value(value: Option<&Value>) -> String {
         Some(Value::String(s)) => s.clone(),
         Some(Value::Number(n)) => n.to_string(),
         Some(Value::Bool(b)) => b.to_string(),
+        Some(Value::Array(arr)) if arr.is_empty() => "[]".to_string(),
         Some(Value::Array(arr)) => {
-            if arr.is_empty() {
-                "[]".to_string()
+            let scalars: Option<Vec<String>> = arr
+                .iter()
+                .map(|v| match v {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Number(_) | Value::Bool(_) => Some(v.to_string()),
+                    _ => None,
+                })
+                .collect();
+            match scalars.map(|items| items.join(", ")) {
+                Some(inline) if inline.chars().count() <= INLINE_CELL_MAX_CHARS => inline,
+                _ => format!("[{} items]", arr.len()),
+            }
+        }
+        Some(value @ Value::Object(_)) => {
+            let compact = value.to_string();
+            if compact.chars().count() <= INLINE_CELL_MAX_CHARS {
+                compact
             } else {
-                format!("[{} items]", arr.len())
+                "{...}".to_string()
             }
         }
-        Some(Value::Object(_)) => "{...}".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv(items: &[Value], format: &Format) -> String {
+        let mut out = Vec::new();
+        write_delimited(&mut out, items, format, &[]).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_csv_union_columns_and_flattening() {
+        let items = vec![
+            json!({"id": "a", "attributes": {"http": {"status_code": 500}}, "tags": ["env:prod", "team:x"]}),
+            json!({"id": "b", "host": "web-1", "tags": []}),
+        ];
+        let format = Format::from_str("csv").unwrap();
+
+        assert_eq!(
+            csv(&items, &format),
+            "attributes.http.status_code,id,tags,host\r\n\
+             500,a,env:prod;team:x,\r\n\
+             ,b,,web-1\r\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_quoting_and_array_separator() {
+        let items = vec![
+            json!({"message": "said \"hi\", then\nleft", "tags": ["a", "b"], "note": " padded"}),
+        ];
+        let format = Format::from_str("csv").unwrap().with_array_separator("|");
+
+        assert_eq!(
+            csv(&items, &format),
+            "message,note,tags\r\n\"said \"\"hi\"\", then\nleft\",\" padded\",a|b\r\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_columns_follow_field_order() {
+        let items = vec![json!({"timestamp": "t1", "id": "a", "service": "web", "extra": 1})];
+        let order = vec![
+            "timestamp".to_string(),
+            "service".to_string(),
+            "id".to_string(),
+        ];
+        let mut out = Vec::new();
+        write_delimited(&mut out, &items, &Format::from_str("csv").unwrap(), &order).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "timestamp,service,id,extra\r\nt1,web,a,1\r\n"
+        );
+    }
+
+    #[test]
+    fn test_tsv_quotes_only_when_needed() {
+        let items = vec![json!({"a": "x,y", "b": "tab\there"})];
+        let format = Format::from_str("tsv").unwrap();
+
+        assert_eq!(csv(&items, &format), "a\tb\r\nx,y\t\"tab\there\"\r\n");
+    }
+
+    #[test]
+    fn test_table_row_flattens_one_level() {
+        let row = table_row(&json!({
+            "id": "abc",
+            "attributes": {"service": "api", "http": {"status_code": 500}},
+            "meta": {},
+        }));
+
+        assert_eq!(
+            row.keys().collect::<Vec<_>>(),
+            vec!["attributes.http", "attributes.service", "id", "meta"]
+        );
+        assert_eq!(
+            format_value(row.get("attributes.http")),
+            r#"{"status_code":500}"#
+        );
+    }
+
+    #[test]
+    fn test_format_value_inline_arrays() {
+        assert_eq!(
+            format_value(Some(&json!(["env:prod", "team:x"]))),
+            "env:prod, team:x"
+        );
+        assert_eq!(format_value(Some(&json!([{"a": 1}]))), "[1 items]");
+        let long: Vec<String> = (0..20).map(|i| format!("tag-number-{}", i)).collect();
+        assert_eq!(format_value(Some(&json!(long))), "[20 items]");
+        assert_eq!(format_value(Some(&json!(null))), "-");
+    }
+
+    #[test]
+    fn test_follow_stream_is_paged_before_first_page() {
+        let format = Format::JsonLines;
+        let mut pages = PageStream::new(&format, None, None);
+        assert!(!pages.is_paged());
+        pages.follow();
+        assert!(pages.is_paged());
+    }
+
+    #[test]
+    fn test_pagination_footer() {
+        assert_eq!(pagination_footer(None, 3), None);
+        assert_eq!(
+            pagination_footer(
+                Some(&json!({"page": 0, "has_next": true, "next_cursor": "abc"})),
+                10
+            ),
+            Some("10 rows · page 1 · next cursor: abc".to_string())
+        );
+        assert_eq!(
+            pagination_footer(
+                Some(&json!({"page": 1, "has_next": true, "next_offset": 200})),
+                100
+            ),
+            Some("100 rows · page 2 · more from offset 200".to_string())
+        );
+        assert_eq!(
+            pagination_footer(
+                Some(&json!({"page": 1, "has_next": true, "next_page": 2})),
+                100
+            ),
+            Some("100 rows · page 2 · more from --page 2".to_string())
+        );
+        assert_eq!(
+            pagination_footer(Some(&json!({"page": 0, "has_next": false})), 4),
+            Some("4 rows · page 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_detail_tables_sections() {
+        let monitor = json!({
+            "id": 42,
+            "name": "High CPU",
+            "tags": ["env:prod"],
+            "options": {"notify_no_data": false, "thresholds": {"critical": 90.0, "warning": 80.0}},
+            "creator": {"handle": "ops@example.com"},
+            "widgets": [{"id": 1, "definition": {"type": "timeseries", "title": "CPU"}}],
+        });
+        let rendered = detail_tables(None, monitor.as_object().unwrap(), &[]);
+
+        let titles: Vec<&str> = rendered
+            .lines()
+            .filter(|l| !l.is_empty() && !l.starts_with(['┌', '│', '├', '└', '╞']))
+            .collect();
+        assert_eq!(
+            titles,
+            vec!["creator", "options", "options.thresholds", "widgets (1)"]
+        );
+        assert!(rendered.contains("High CPU"));
+        assert!(rendered.contains("env:prod"));
+        assert!(rendered.contains("definition.title"));
+    }
+
+    #[test]
+    fn test_rows_from_detail_object() {
+        let detail = json!({"data": {"id": 1, "name": "cpu"}});
+        assert_eq!(
+            csv(&rows(&detail), &Format::from_str("csv").unwrap()),
+            "id,name\r\n1,cpu\r\n"
+        );
     }
 }
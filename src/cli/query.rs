@@ -0,0 +1,1315 @@
+use serde_json::{Map, Number, Value};
+use std::cmp::Ordering;
+
+use crate::error::{DatadogError, Result};
+
+/// A `--query` expression: a subset of jq evaluated against the command result
+///
+/// Supported: `.`, `.field`, `."quoted key"`, `.[n]`, `.[a:b]`, `.[]`, `?`,
+/// `|`, `,`, `//`, `and`/`or`, comparisons, `+ - * / %`, array and object
+/// construction, literals and the builtins listed in `call`.
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.pipe()?;
+        if let Some(token) = parser.peek() {
+            return Err(invalid(format!("unexpected {:?}", token)));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Run the filter; it must produce at most one value, `null` when it produces none
+    pub fn apply(&self, input: &Value) -> Result<Value> {
+        let mut outputs = eval(&self.expr, input)?.into_iter();
+        match (outputs.next(), outputs.next()) {
+            (None, _) => Ok(Value::Null),
+            (Some(value), None) => Ok(value),
+            (Some(_), Some(_)) => Err(invalid(format!(
+                "query produced {} results; wrap it in [...] to collect them into an array",
+                outputs.len() + 2
+            ))),
+        }
+    }
+}
+
+fn invalid(message: impl std::fmt::Display) -> DatadogError {
+    DatadogError::InvalidInput(format!("--query: {}", message))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Pipe,
+    Comma,
+    Colon,
+    Semicolon,
+    Question,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '.' => {
+                tokens.push(Token::Dot);
+                i += 1;
+            }
+            '[' | ']' | '(' | ')' | '{' | '}' | ',' | ':' | ';' | '?' => {
+                tokens.push(match c {
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '{' => Token::LBrace,
+                    '}' => Token::RBrace,
+                    ',' => Token::Comma,
+                    ':' => Token::Colon,
+                    ';' => Token::Semicolon,
+                    _ => Token::Question,
+                });
+                i += 1;
+            }
+            '|' => {
+                tokens.push(Token::Pipe);
+                i += 1;
+            }
+            '"' => {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(invalid("unterminated string")),
+                        Some('"') => break,
+                        Some('\\') => {
+                            text.push(match chars.get(i + 1) {
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                Some(&other) => other,
+                                None => return Err(invalid("unterminated string")),
+                            });
+                            i += 2;
+                        }
+                        Some(&other) => {
+                            text.push(other);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push(Token::Str(text));
+                i += 1;
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid number '{}'", text)))?;
+                tokens.push(Token::Num(number));
+            }
+            c if c.is_alphabetic() || c == '_' || c == '$' || c == '@' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '$' | '@'))
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            _ => {
+                let two: String = [c, next.unwrap_or(' ')].iter().collect();
+                let op = match two.as_str() {
+                    "==" => "==",
+                    "!=" => "!=",
+                    "<=" => "<=",
+                    ">=" => ">=",
+                    "//" => "//",
+                    _ => match c {
+                        '<' => "<",
+                        '>' => ">",
+                        '+' => "+",
+                        '-' => "-",
+                        '*' => "*",
+                        '/' => "/",
+                        '%' => "%",
+                        _ => return Err(invalid(format!("unexpected character '{}'", c))),
+                    },
+                };
+                tokens.push(Token::Op(op));
+                i += op.len();
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Identity,
+    Literal(Value),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Slice(Box<Expr>, Option<Box<Expr>>, Option<Box<Expr>>),
+    Iterate(Box<Expr>),
+    Try(Box<Expr>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Alternative(Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Call(String, Vec<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<()> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            Some(t) => Err(invalid(format!("expected {:?}, found {:?}", token, t))),
+            None => Err(invalid(format!("expected {:?} at end of query", token))),
+        }
+    }
+
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(k)) if k == keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn pipe(&mut self) -> Result<Expr> {
+        let mut expr = self.comma()?;
+        while self.eat(&Token::Pipe) {
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.comma()?));
+        }
+        Ok(expr)
+    }
+
+    fn comma(&mut self) -> Result<Expr> {
+        let mut expr = self.alternative()?;
+        while self.eat(&Token::Comma) {
+            expr = Expr::Comma(Box::new(expr), Box::new(self.alternative()?));
+        }
+        Ok(expr)
+    }
+
+    fn alternative(&mut self) -> Result<Expr> {
+        let mut expr = self.or()?;
+        while self.eat_op(&["//"]).is_some() {
+            expr = Expr::Alternative(Box::new(expr), Box::new(self.or()?));
+        }
+        Ok(expr)
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.comparison()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.comparison()?));
+        }
+        Ok(expr)
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let expr = self.additive()?;
+        match self.eat_op(&["==", "!=", "<", "<=", ">", ">="]) {
+            Some(op) => Ok(Expr::Binary(op, Box::new(expr), Box::new(self.additive()?))),
+            None => Ok(expr),
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr> {
+        let mut expr = self.multiplicative()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.multiplicative()?));
+        }
+        Ok(expr)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr> {
+        let mut expr = self.postfix()?;
+        while let Some(op) = self.eat_op(&["*", "/", "%"]) {
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.postfix()?));
+        }
+        Ok(expr)
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            expr = match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(Token::LBracket) => {
+                            self.pos += 1;
+                            self.bracket(expr)?
+                        }
+                        _ => Expr::Field(Box::new(expr), self.field_name()?),
+                    }
+                }
+                Some(Token::LBracket) => {
+                    self.pos += 1;
+                    self.bracket(expr)?
+                }
+                Some(Token::Question) => {
+                    self.pos += 1;
+                    Expr::Try(Box::new(expr))
+                }
+                _ => return Ok(expr),
+            };
+        }
+    }
+
+    fn field_name(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Ident(name)) | Some(Token::Str(name)) => Ok(name),
+            Some(t) => Err(invalid(format!(
+                "expected field name after '.', found {:?}",
+                t
+            ))),
+            None => Err(invalid("expected field name after '.'")),
+        }
+    }
+
+    /// Parse what follows `[`: iteration, an index or a slice
+    fn bracket(&mut self, target: Expr) -> Result<Expr> {
+        let target = Box::new(target);
+        if self.eat(&Token::RBracket) {
+            return Ok(Expr::Iterate(target));
+        }
+
+        let start = if self.peek() == Some(&Token::Colon) {
+            None
+        } else {
+            Some(Box::new(self.pipe()?))
+        };
+
+        if self.eat(&Token::Colon) {
+            let end = if self.peek() == Some(&Token::RBracket) {
+                None
+            } else {
+                Some(Box::new(self.pipe()?))
+            };
+            self.expect(Token::RBracket)?;
+            return Ok(Expr::Slice(target, start, end));
+        }
+
+        self.expect(Token::RBracket)?;
+        let index = start.ok_or_else(|| invalid("empty index"))?;
+        Ok(Expr::Index(target, index))
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Dot) => match self.peek() {
+                Some(Token::Ident(_)) | Some(Token::Str(_)) => {
+                    Ok(Expr::Field(Box::new(Expr::Identity), self.field_name()?))
+                }
+                Some(Token::LBracket) => {
+                    self.pos += 1;
+                    self.bracket(Expr::Identity)
+                }
+                _ => Ok(Expr::Identity),
+            },
+            Some(Token::Num(n)) => Ok(Expr::Literal(number(n))),
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::Op("-")) => Ok(Expr::Neg(Box::new(self.postfix()?))),
+            Some(Token::LParen) => {
+                let expr = self.pipe()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::LBracket) => {
+                if self.eat(&Token::RBracket) {
+                    return Ok(Expr::Array(None));
+                }
+                let expr = self.pipe()?;
+                self.expect(Token::RBracket)?;
+                Ok(Expr::Array(Some(Box::new(expr))))
+            }
+            Some(Token::LBrace) => self.object(),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ => {
+                    let mut args = Vec::new();
+                    if self.eat(&Token::LParen) {
+                        args.push(self.pipe()?);
+                        while self.eat(&Token::Semicolon) {
+                            args.push(self.pipe()?);
+                        }
+                        self.expect(Token::RParen)?;
+                    }
+                    Ok(Expr::Call(name, args))
+                }
+            },
+            Some(t) => Err(invalid(format!("unexpected {:?}", t))),
+            None => Err(invalid("unexpected end of query")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Expr> {
+        let mut entries = Vec::new();
+        if self.eat(&Token::RBrace) {
+            return Ok(Expr::Object(entries));
+        }
+
+        loop {
+            let (key, shorthand) = match self.next() {
+                Some(Token::Ident(name)) | Some(Token::Str(name)) => (
+                    Expr::Literal(Value::String(name.clone())),
+                    Expr::Field(Box::new(Expr::Identity), name),
+                ),
+                Some(Token::LParen) => {
+                    let key = self.pipe()?;
+                    self.expect(Token::RParen)?;
+                    (key, Expr::Literal(Value::Null))
+                }
+                Some(t) => return Err(invalid(format!("unexpected {:?} in object", t))),
+                None => return Err(invalid("unterminated object")),
+            };
+
+            let value = if self.eat(&Token::Colon) {
+                self.alternative()?
+            } else {
+                shorthand
+            };
+            entries.push((key, value));
+
+            if self.eat(&Token::RBrace) {
+                return Ok(Expr::Object(entries));
+            }
+            self.expect(Token::Comma)?;
+        }
+    }
+}
+
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Value::from(n as i64)
+    } else {
+        Number::from_f64(n).map_or(Value::Null, Value::Number)
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// jq ordering: null < false < true < numbers < strings < arrays < objects
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            let (x, y) = (x.as_f64().unwrap_or(0.0), y.as_f64().unwrap_or(0.0));
+            x.partial_cmp(&y).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| compare(x, y))
+            .find(|o| o.is_ne())
+            .unwrap_or(x.len().cmp(&y.len())),
+        (Value::Object(x), Value::Object(y)) => {
+            let mut xk: Vec<_> = x.keys().collect();
+            let mut yk: Vec<_> = y.keys().collect();
+            xk.sort();
+            yk.sort();
+            xk.cmp(&yk).then_with(|| {
+                xk.iter()
+                    .map(|k| compare(&x[*k], &y[*k]))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn as_f64(value: &Value, op: &str) -> Result<f64> {
+    value.as_f64().ok_or_else(|| {
+        invalid(format!(
+            "cannot apply '{}' to {} ({})",
+            op,
+            type_name(value),
+            value
+        ))
+    })
+}
+
+fn binary(op: &str, a: &Value, b: &Value) -> Result<Value> {
+    Ok(match op {
+        "==" => Value::Bool(compare(a, b).is_eq()),
+        "!=" => Value::Bool(compare(a, b).is_ne()),
+        "<" => Value::Bool(compare(a, b).is_lt()),
+        "<=" => Value::Bool(compare(a, b).is_le()),
+        ">" => Value::Bool(compare(a, b).is_gt()),
+        ">=" => Value::Bool(compare(a, b).is_ge()),
+        "+" => match (a, b) {
+            (Value::Null, other) | (other, Value::Null) => other.clone(),
+            (Value::String(x), Value::String(y)) => Value::String(format!("{}{}", x, y)),
+            (Value::Array(x), Value::Array(y)) => {
+                Value::Array(x.iter().chain(y).cloned().collect())
+            }
+            (Value::Object(x), Value::Object(y)) => {
+                let mut merged = x.clone();
+                merged.extend(y.clone());
+                Value::Object(merged)
+            }
+            _ => number(as_f64(a, op)? + as_f64(b, op)?),
+        },
+        "-" => match (a, b) {
+            (Value::Array(x), Value::Array(y)) => {
+                Value::Array(x.iter().filter(|v| !y.contains(v)).cloned().collect())
+            }
+            _ => number(as_f64(a, op)? - as_f64(b, op)?),
+        },
+        "*" => number(as_f64(a, op)? * as_f64(b, op)?),
+        "/" => match (a, b) {
+            (Value::String(x), Value::String(y)) => {
+                Value::Array(x.split(y.as_str()).map(Value::from).collect())
+            }
+            _ => {
+                let divisor = as_f64(b, op)?;
+                if divisor == 0.0 {
+                    return Err(invalid("division by zero"));
+                }
+                number(as_f64(a, op)? / divisor)
+            }
+        },
+        "%" => {
+            let divisor = as_f64(b, op)? as i64;
+            if divisor == 0 {
+                return Err(invalid("modulo by zero"));
+            }
+            let remainder = (as_f64(a, op)? as i64)
+                .checked_rem(divisor)
+                .ok_or_else(|| invalid("modulo overflow"))?;
+            Value::from(remainder)
+        }
+        _ => return Err(invalid(format!("unknown operator '{}'", op))),
+    })
+}
+
+/// Evaluate both sides against `input` and combine every pair of outputs
+fn product(
+    lhs: &Expr,
+    rhs: &Expr,
+    input: &Value,
+    combine: impl Fn(&Value, &Value) -> Result<Value>,
+) -> Result<Vec<Value>> {
+    let rights = eval(rhs, input)?;
+    let mut out = Vec::new();
+    for right in &rights {
+        for left in eval(lhs, input)? {
+            out.push(combine(&left, right)?);
+        }
+    }
+    Ok(out)
+}
+
+fn eval(expr: &Expr, input: &Value) -> Result<Vec<Value>> {
+    match expr {
+        Expr::Identity => Ok(vec![input.clone()]),
+        Expr::Literal(value) => Ok(vec![value.clone()]),
+        Expr::Field(target, name) => eval(target, input)?
+            .iter()
+            .map(|value| match value {
+                Value::Object(obj) => Ok(obj.get(name).cloned().unwrap_or(Value::Null)),
+                Value::Null => Ok(Value::Null),
+                other => Err(invalid(format!(
+                    "cannot index {} with \"{}\"",
+                    type_name(other),
+                    name
+                ))),
+            })
+            .collect(),
+        Expr::Index(target, index) => {
+            let mut out = Vec::new();
+            for value in eval(target, input)? {
+                for key in eval(index, input)? {
+                    out.push(index_value(&value, &key)?);
+                }
+            }
+            Ok(out)
+        }
+        Expr::Slice(target, start, end) => {
+            let bound = |e: &Option<Box<Expr>>| -> Result<Option<i64>> {
+                match e {
+                    Some(e) => Ok(eval(e, input)?.first().and_then(Value::as_i64)),
+                    None => Ok(None),
+                }
+            };
+            let (start, end) = (bound(start)?, bound(end)?);
+            eval(target, input)?
+                .iter()
+                .map(|value| slice(value, start, end))
+                .collect()
+        }
+        Expr::Iterate(target) => {
+            let mut out = Vec::new();
+            for value in eval(target, input)? {
+                match value {
+                    Value::Array(items) => out.extend(items),
+                    Value::Object(obj) => out.extend(obj.into_iter().map(|(_, v)| v)),
+                    other => {
+                        return Err(invalid(format!(
+                            "cannot iterate over {}",
+                            type_name(&other)
+                        )));
+                    }
+                }
+            }
+            Ok(out)
+        }
+        Expr::Try(inner) => Ok(eval(inner, input).unwrap_or_default()),
+        Expr::Pipe(lhs, rhs) => {
+            let mut out = Vec::new();
+            for value in eval(lhs, input)? {
+                out.extend(eval(rhs, &value)?);
+            }
+            Ok(out)
+        }
+        Expr::Comma(lhs, rhs) => {
+            let mut out = eval(lhs, input)?;
+            out.extend(eval(rhs, input)?);
+            Ok(out)
+        }
+        Expr::Binary(op, lhs, rhs) => product(lhs, rhs, input, |a, b| binary(op, a, b)),
+        Expr::And(lhs, rhs) => product(lhs, rhs, input, |a, b| {
+            Ok(Value::Bool(truthy(a) && truthy(b)))
+        }),
+        Expr::Or(lhs, rhs) => product(lhs, rhs, input, |a, b| {
+            Ok(Value::Bool(truthy(a) || truthy(b)))
+        }),
+        Expr::Alternative(lhs, rhs) => {
+            let values: Vec<Value> = eval(lhs, input)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(truthy)
+                .collect();
+            if values.is_empty() {
+                eval(rhs, input)
+            } else {
+                Ok(values)
+            }
+        }
+        Expr::Neg(inner) => eval(inner, input)?
+            .iter()
+            .map(|v| Ok(number(-as_f64(v, "-")?)))
+            .collect(),
+        Expr::Array(None) => Ok(vec![Value::Array(Vec::new())]),
+        Expr::Array(Some(inner)) => Ok(vec![Value::Array(eval(inner, input)?)]),
+        Expr::Object(entries) => {
+            let mut objects = vec![Map::new()];
+            for (key, value) in entries {
+                let keys = eval(key, input)?;
+                let values = eval(value, input)?;
+                let mut next = Vec::new();
+                for object in &objects {
+                    for key in &keys {
+                        let key = key.as_str().ok_or_else(|| {
+                            invalid(format!(
+                                "object key must be a string, got {}",
+                                type_name(key)
+                            ))
+                        })?;
+                        for value in &values {
+                            let mut object = object.clone();
+                            object.insert(key.to_string(), value.clone());
+                            next.push(object);
+                        }
+                    }
+                }
+                objects = next;
+            }
+            Ok(objects.into_iter().map(Value::Object).collect())
+        }
+        Expr::Call(name, args) => call(name, args, input),
+    }
+}
+
+fn index_value(value: &Value, key: &Value) -> Result<Value> {
+    match (value, key) {
+        (Value::Null, _) => Ok(Value::Null),
+        (Value::Object(obj), Value::String(k)) => Ok(obj.get(k).cloned().unwrap_or(Value::Null)),
+        (Value::Array(items), Value::Number(n)) => {
+            let i = n.as_f64().unwrap_or(0.0) as i64;
+            let i = if i < 0 { items.len() as i64 + i } else { i };
+            Ok(usize::try_from(i)
+                .ok()
+                .and_then(|i| items.get(i))
+                .cloned()
+                .unwrap_or(Value::Null))
+        }
+        _ => Err(invalid(format!(
+            "cannot index {} with {}",
+            type_name(value),
+            type_name(key)
+        ))),
+    }
+}
+
+fn slice(value: &Value, start: Option<i64>, end: Option<i64>) -> Result<Value> {
+    let bounds = |len: usize| {
+        let clamp = |i: i64| {
+            let i = if i < 0 { len as i64 + i } else { i };
+            i.clamp(0, len as i64) as usize
+        };
+        let start = start.map_or(0, clamp);
+        let end = end.map_or(len, clamp);
+        (start, end.max(start))
+    };
+
+    match value {
+        Value::Null => Ok(Value::Null),
+        Value::Array(items) => {
+            let (start, end) = bounds(items.len());
+            Ok(Value::Array(items[start..end].to_vec()))
+        }
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let (start, end) = bounds(chars.len());
+            Ok(Value::String(chars[start..end].iter().collect()))
+        }
+        other => Err(invalid(format!("cannot slice {}", type_name(other)))),
+    }
+}
+
+fn expect_array<'a>(name: &str, input: &'a Value) -> Result<&'a Vec<Value>> {
+    input.as_array().ok_or_else(|| {
+        invalid(format!(
+            "{} expects an array, got {}",
+            name,
+            type_name(input)
+        ))
+    })
+}
+
+fn single(expr: &Expr, input: &Value) -> Result<Value> {
+    Ok(eval(expr, input)?.into_iter().next().unwrap_or(Value::Null))
+}
+
+/// Sort `items` by the first output of `key` for each, keeping ties in order
+fn sorted_by(items: &[Value], key: &Expr) -> Result<Vec<(Value, Value)>> {
+    let mut keyed = items
+        .iter()
+        .map(|item| Ok((single(key, item)?, item.clone())))
+        .collect::<Result<Vec<_>>>()?;
+    keyed.sort_by(|a, b| compare(&a.0, &b.0));
+    Ok(keyed)
+}
+
+fn call(name: &str, args: &[Expr], input: &Value) -> Result<Vec<Value>> {
+    let arity = |n: usize| {
+        if args.len() == n {
+            Ok(())
+        } else {
+            Err(invalid(format!("{} takes {} argument(s)", name, n)))
+        }
+    };
+
+    let value = match name {
+        "empty" => {
+            arity(0)?;
+            return Ok(Vec::new());
+        }
+        "select" => {
+            arity(1)?;
+            let keep = eval(&args[0], input)?.iter().any(truthy);
+            return Ok(if keep {
+                vec![input.clone()]
+            } else {
+                Vec::new()
+            });
+        }
+        "length" => {
+            arity(0)?;
+            match input {
+                Value::Null => Value::from(0),
+                Value::Bool(_) => return Err(invalid("boolean has no length")),
+                Value::Number(n) => number(n.as_f64().unwrap_or(0.0).abs()),
+                Value::String(s) => Value::from(s.chars().count()),
+                Value::Array(items) => Value::from(items.len()),
+                Value::Object(obj) => Value::from(obj.len()),
+            }
+        }
+        "keys" => {
+            arity(0)?;
+            match input {
+                Value::Object(obj) => {
+                    let mut keys: Vec<&String> = obj.keys().collect();
+                    keys.sort();
+                    Value::from(keys.into_iter().cloned().collect::<Vec<_>>())
+                }
+                Value::Array(items) => Value::from((0..items.len()).collect::<Vec<_>>()),
+                other => return Err(invalid(format!("{} has no keys", type_name(other)))),
+            }
+        }
+        "values" => {
+            arity(0)?;
+            match input {
+                Value::Object(obj) => Value::Array(obj.values().cloned().collect()),
+                Value::Array(items) => Value::Array(items.clone()),
+                other => return Err(invalid(format!("{} has no values", type_name(other)))),
+            }
+        }
+        "has" => {
+            arity(1)?;
+            let key = single(&args[0], input)?;
+            Value::Bool(match (input, &key) {
+                (Value::Object(obj), Value::String(k)) => obj.contains_key(k),
+                (Value::Array(items), Value::Number(n)) => {
+                    n.as_u64().is_some_and(|i| (i as usize) < items.len())
+                }
+                _ => return Err(invalid("has expects an object key or an array index")),
+            })
+        }
+        "map" => {
+            arity(1)?;
+            let mut out = Vec::new();
+            for item in expect_array(name, input)? {
+                out.extend(eval(&args[0], item)?);
+            }
+            Value::Array(out)
+        }
+        "sort" => {
+            arity(0)?;
+            let mut items = expect_array(name, input)?.clone();
+            items.sort_by(compare);
+            Value::Array(items)
+        }
+        "sort_by" => {
+            arity(1)?;
+            let keyed = sorted_by(expect_array(name, input)?, &args[0])?;
+            Value::Array(keyed.into_iter().map(|(_, item)| item).collect())
+        }
+        "group_by" => {
+            arity(1)?;
+            let mut groups: Vec<(Value, Vec<Value>)> = Vec::new();
+            for (key, item) in sorted_by(expect_array(name, input)?, &args[0])? {
+                match groups.last_mut() {
+                    Some((last, group)) if compare(last, &key).is_eq() => group.push(item),
+                    _ => groups.push((key, vec![item])),
+                }
+            }
+            Value::Array(groups.into_iter().map(|(_, g)| Value::Array(g)).collect())
+        }
+        "unique" => {
+            arity(0)?;
+            let mut items = expect_array(name, input)?.clone();
+            items.sort_by(compare);
+            items.dedup();
+            Value::Array(items)
+        }
+        "min" | "max" => {
+            arity(0)?;
+            let items = expect_array(name, input)?.iter();
+            let found = if name == "min" {
+                items.min_by(|a, b| compare(a, b))
+            } else {
+                items.max_by(|a, b| compare(a, b))
+            };
+            found.cloned().unwrap_or(Value::Null)
+        }
+        "add" => {
+            arity(0)?;
+            let items = match input {
+                Value::Object(obj) => obj.values().cloned().collect(),
+                other => expect_array(name, other)?.clone(),
+            };
+            items
+                .iter()
+                .try_fold(Value::Null, |acc, item| binary("+", &acc, item))?
+        }
+        "first" | "last" => {
+            if let [arg] = args {
+                let outputs = eval(arg, input)?;
+                let found = if name == "first" {
+                    outputs.into_iter().next()
+                } else {
+                    outputs.into_iter().last()
+                };
+                return Ok(found.into_iter().collect());
+            }
+            arity(0)?;
+            index_value(input, &Value::from(if name == "first" { 0 } else { -1 }))?
+        }
+        "reverse" => {
+            arity(0)?;
+            match input {
+                Value::String(s) => Value::String(s.chars().rev().collect()),
+                Value::Null => Value::Array(Vec::new()),
+                other => Value::Array(expect_array(name, other)?.iter().rev().cloned().collect()),
+            }
+        }
+        "not" => {
+            arity(0)?;
+            Value::Bool(!truthy(input))
+        }
+        "type" => {
+            arity(0)?;
+            Value::from(type_name(input))
+        }
+        "to_entries" => {
+            arity(0)?;
+            let obj = input
+                .as_object()
+                .ok_or_else(|| invalid("to_entries expects an object"))?;
+            Value::Array(
+                obj.iter()
+                    .map(|(k, v)| serde_json::json!({ "key": k, "value": v }))
+                    .collect(),
+            )
+        }
+        "from_entries" => {
+            arity(0)?;
+            let mut obj = Map::new();
+            for entry in expect_array(name, input)? {
+                let key = match entry.get("key").or_else(|| entry.get("name")) {
+                    Some(Value::String(k)) => k.clone(),
+                    Some(other) if !other.is_null() => other.to_string(),
+                    _ => return Err(invalid("from_entries expects entries with a key")),
+                };
+                obj.insert(key, entry.get("value").cloned().unwrap_or(Value::Null));
+            }
+            Value::Object(obj)
+        }
+        "tostring" => {
+            arity(0)?;
+            match input {
+                Value::String(s) => Value::String(s.clone()),
+                other => Value::String(other.to_string()),
+            }
+        }
+        "tonumber" => {
+            arity(0)?;
+            match input {
+                Value::Number(_) => input.clone(),
+                Value::String(s) => number(
+                    s.trim()
+                        .parse()
+                        .map_err(|_| invalid(format!("cannot parse '{}' as a number", s)))?,
+                ),
+                other => {
+                    return Err(invalid(format!(
+                        "cannot convert {} to a number",
+                        type_name(other)
+                    )));
+                }
+            }
+        }
+        "ascii_downcase" | "ascii_upcase" => {
+            arity(0)?;
+            let s = input
+                .as_str()
+                .ok_or_else(|| invalid(format!("{} expects a string", name)))?;
+            Value::String(if name == "ascii_downcase" {
+                s.to_ascii_lowercase()
+            } else {
+                s.to_ascii_uppercase()
+            })
+        }
+        "startswith" | "endswith" | "contains" | "join" | "split" => {
+            arity(1)?;
+            let arg = single(&args[0], input)?;
+            match (name, input, &arg) {
+                ("startswith", Value::String(s), Value::String(p)) => {
+                    Value::Bool(s.starts_with(p.as_str()))
+                }
+                ("endswith", Value::String(s), Value::String(p)) => {
+                    Value::Bool(s.ends_with(p.as_str()))
+                }
+                ("contains", Value::String(s), Value::String(p)) => {
+                    Value::Bool(s.contains(p.as_str()))
+                }
+                ("contains", Value::Array(items), Value::Array(wanted)) => {
+                    Value::Bool(wanted.iter().all(|w| items.contains(w)))
+                }
+                ("split", Value::String(s), Value::String(sep)) => {
+                    Value::Array(s.split(sep.as_str()).map(Value::from).collect())
+                }
+                ("join", Value::Array(items), Value::String(sep)) => Value::String(
+                    items
+                        .iter()
+                        .map(|item| match item {
+                            Value::String(s) => s.clone(),
+                            Value::Null => String::new(),
+                            other => other.to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(sep),
+                ),
+                _ => {
+                    return Err(invalid(format!(
+                        "{} cannot be applied to {} and {}",
+                        name,
+                        type_name(input),
+                        type_name(&arg)
+                    )));
+                }
+            }
+        }
+        _ => return Err(invalid(format!("unknown function '{}'", name))),
+    };
+
+    Ok(vec![value])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(query: &str, input: &Value) -> Value {
+        Filter::parse(query).unwrap().apply(input).unwrap()
+    }
+
+    fn sample() -> Value {
+        json!({
+            "data": [
+                {"id": "1", "service": "api", "status": "error", "duration": 120, "tags": ["env:prod"]},
+                {"id": "2", "service": "web", "status": "info", "duration": 30, "tags": []},
+                {"id": "3", "service": "api", "status": "error", "duration": 300, "tags": ["env:dev"]},
+            ],
+            "meta": {"count": 3},
+        })
+    }
+
+    #[test]
+    fn test_paths_and_iteration() {
+        let input = sample();
+        assert_eq!(run(".", &input), input);
+        assert_eq!(run(".meta.count", &input), json!(3));
+        assert_eq!(run(".data[0].id", &input), json!("1"));
+        assert_eq!(run(".data[-1].id", &input), json!("3"));
+        assert_eq!(run("[.data[].id]", &input), json!(["1", "2", "3"]));
+        assert_eq!(run(".data[1:].id?", &input), Value::Null);
+        assert_eq!(run("[.data[1:][] | .id]", &input), json!(["2", "3"]));
+        assert_eq!(run(".missing.deeper", &input), Value::Null);
+        assert_eq!(run(r#"."meta"["count"]"#, &input), json!(3));
+    }
+
+    #[test]
+    fn test_select_map_and_construction() {
+        let input = sample();
+        assert_eq!(
+            run(
+                r#"[.data[] | select(.status == "error" and .duration > 200) | .id]"#,
+                &input
+            ),
+            json!(["3"])
+        );
+        assert_eq!(
+            run(".data | map({id, ms: .duration * 2})", &input),
+            json!([{"id": "1", "ms": 240}, {"id": "2", "ms": 60}, {"id": "3", "ms": 600}])
+        );
+        assert_eq!(run(".data | map(.duration) | add", &input), json!(450));
+        assert_eq!(run(".data | length", &input), json!(3));
+        assert_eq!(
+            run(
+                ".data | group_by(.service) | map({service: .[0].service, n: length})",
+                &input
+            ),
+            json!([{"service": "api", "n": 2}, {"service": "web", "n": 1}])
+        );
+        assert_eq!(
+            run(".data | sort_by(.duration) | last | .id", &input),
+            json!("3")
+        );
+        assert_eq!(
+            run(r#".data[0].tags | join(",")"#, &input),
+            json!("env:prod")
+        );
+        assert_eq!(run(".meta.nope // \"fallback\"", &input), json!("fallback"));
+        assert_eq!(run("[.data[0].id, .data[2].id]", &input), json!(["1", "3"]));
+    }
+
+    #[test]
+    fn test_errors_are_invalid_input() {
+        for query in [
+            ".data[",
+            "select(",
+            ".data | frobnicate",
+            "{a: }",
+            ".a ==",
+            "'x'",
+        ] {
+            let err = Filter::parse(query).and_then(|f| f.apply(&sample()));
+            assert!(
+                matches!(err, Err(DatadogError::InvalidInput(_))),
+                "{}",
+                query
+            );
+        }
+
+        let err = Filter::parse(".data.id").unwrap().apply(&sample());
+        assert!(matches!(err, Err(DatadogError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_output_count() {
+        let input = sample();
+        assert_eq!(run("empty", &input), Value::Null);
+        assert_eq!(
+            run(".data[] | select(.id == \"2\") | .service", &input),
+            json!("web")
+        );
+
+        match Filter::parse(".data[].id").unwrap().apply(&input) {
+            Err(DatadogError::InvalidInput(message)) => {
+                assert!(message.contains("3 results"), "{}", message);
+                assert!(message.contains("[...]"), "{}", message);
+            }
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_operators() {
+        let cases = [
+            ("1 + 2", json!(null), json!(3)),
+            ("null + 1", json!(null), json!(1)),
+            (r#""a" + "b""#, json!(null), json!("ab")),
+            ("[1] + [2]", json!(null), json!([1, 2])),
+            (
+                "{a: 1} + {a: 2, b: 3}",
+                json!(null),
+                json!({"a": 2, "b": 3}),
+            ),
+            ("[1, 2, 3, 2] - [2]", json!(null), json!([1, 3])),
+            ("5 - 7", json!(null), json!(-2)),
+            ("2 * 3.5", json!(null), json!(7)),
+            ("10 / 4", json!(null), json!(2.5)),
+            (r#""a,b" / ",""#, json!(null), json!(["a", "b"])),
+            ("7 % 3", json!(null), json!(1)),
+            ("-7 % 3", json!(null), json!(-1)),
+            ("-.x", json!({"x": 4}), json!(-4)),
+            ("1 == 1.0", json!(null), json!(true)),
+            ("1 != 2", json!(null), json!(true)),
+            (r#"1 < "a""#, json!(null), json!(true)),
+            ("null < false", json!(null), json!(true)),
+            ("[1, 2] < [1, 3]", json!(null), json!(true)),
+            ("3 >= 3 and 2 > 3", json!(null), json!(false)),
+            ("false or 1 <= 1", json!(null), json!(true)),
+            (".x // 1", json!({}), json!(1)),
+            ("false // \"x\"", json!(null), json!("x")),
+            ("0 // 1", json!(null), json!(0)),
+            (".a // .b // 3", json!({}), json!(3)),
+            (".x.y // 2", json!({"x": 1}), json!(2)),
+            (".x.y?", json!({"x": 1}), json!(null)),
+        ];
+        for (query, input, expected) in cases {
+            assert_eq!(run(query, &input), expected, "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_builtins() {
+        let cases = [
+            (".[1:3]", json!([1, 2, 3, 4]), json!([2, 3])),
+            (".[-2:]", json!([1, 2, 3, 4]), json!([3, 4])),
+            (".[:1]", json!([1, 2, 3, 4]), json!([1])),
+            (".[1:3]", json!("hello"), json!("el")),
+            (".[3:1]", json!([1, 2, 3, 4]), json!([])),
+            (r#"has("a")"#, json!({"a": null}), json!(true)),
+            (r#"has("b")"#, json!({"a": 1}), json!(false)),
+            ("has(1)", json!([1, 2]), json!(true)),
+            ("has(2)", json!([1, 2]), json!(false)),
+            ("keys", json!({"b": 1, "a": 2}), json!(["a", "b"])),
+            ("keys", json!(["x", "y"]), json!([0, 1])),
+            ("values", json!({"a": 1}), json!([1])),
+            (
+                "to_entries",
+                json!({"a": 1, "b": 2}),
+                json!([{"key": "a", "value": 1}, {"key": "b", "value": 2}]),
+            ),
+            (
+                "from_entries",
+                json!([{"key": "a", "value": 1}, {"name": "b", "value": 2}, {"key": 3}]),
+                json!({"a": 1, "b": 2, "3": null}),
+            ),
+            (
+                "to_entries | from_entries",
+                json!({"a": [1]}),
+                json!({"a": [1]}),
+            ),
+            (
+                "unique",
+                json!([3, 1, 3, "a", 2, "a"]),
+                json!([1, 2, 3, "a"]),
+            ),
+            ("min", json!([3, 1, 2]), json!(1)),
+            ("max", json!([3, 1, 2]), json!(3)),
+            ("min", json!([]), json!(null)),
+            ("sort", json!([3, null, "a", 1]), json!([null, 1, 3, "a"])),
+            ("add", json!({"a": 1, "b": 2}), json!(3)),
+            ("add", json!([]), json!(null)),
+            ("first", json!([1, 2]), json!(1)),
+            ("last", json!([1, 2]), json!(2)),
+            ("first(.[] | select(. > 1))", json!([1, 2, 3]), json!(2)),
+            ("last(.[])", json!([1, 2, 3]), json!(3)),
+            ("reverse", json!([1, 2]), json!([2, 1])),
+            ("reverse", json!("ab"), json!("ba")),
+            ("length", json!("héllo"), json!(5)),
+            ("length", json!(-3), json!(3)),
+            ("length", json!(null), json!(0)),
+            ("not", json!(null), json!(true)),
+            ("type", json!([]), json!("array")),
+            ("tostring", json!({"a": 1}), json!(r#"{"a":1}"#)),
+            ("tostring", json!("x"), json!("x")),
+            ("tonumber", json!("42"), json!(42)),
+            ("tonumber", json!(" 1.5 "), json!(1.5)),
+            ("tonumber", json!(7), json!(7)),
+            ("ascii_downcase", json!("AbC"), json!("abc")),
+            ("ascii_upcase", json!("AbC"), json!("ABC")),
+            (r#"split(",")"#, json!("a,b,,c"), json!(["a", "b", "", "c"])),
+            (r#"join("-")"#, json!(["a", 1, null]), json!("a-1-")),
+            (r#"startswith("ab")"#, json!("abc"), json!(true)),
+            (r#"endswith("ab")"#, json!("abc"), json!(false)),
+            (r#"contains("b")"#, json!("abc"), json!(true)),
+            ("contains([1, 3])", json!([1, 2, 3]), json!(true)),
+            ("map(. * 2)", json!([1, 2]), json!([2, 4])),
+            ("[.[] | select(. > 1)]", json!([1, 2, 3]), json!([2, 3])),
+            ("[empty]", json!(null), json!([])),
+        ];
+        for (query, input, expected) in cases {
+            assert_eq!(run(query, &input), expected, "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_evaluation_errors() {
+        let cases = [
+            ("1 % 0", json!(null)),
+            ("-9223372036854775808 % -1", json!(null)),
+            ("1 / 0", json!(null)),
+            (r#""a" - 1"#, json!(null)),
+            ("{} * 2", json!(null)),
+            ("has(1)", json!({})),
+            ("has(1; 2)", json!([])),
+            ("tonumber", json!("x")),
+            ("tonumber", json!(null)),
+            ("keys", json!(5)),
+            ("values", json!("x")),
+            ("from_entries", json!("x")),
+            ("from_entries", json!([{}])),
+            ("to_entries", json!([])),
+            ("sort", json!(1)),
+            ("map(.)", json!({})),
+            (".[]", json!(1)),
+            (".[0]", json!({})),
+            (".[1:]", json!(1)),
+            ("length", json!(true)),
+            ("join(1)", json!([])),
+            ("ascii_downcase", json!(1)),
+            ("{(1): 2}", json!(null)),
+            ("length(1)", json!(null)),
+        ];
+        for (query, input) in cases {
+            let err = Filter::parse(query).and_then(|f| f.apply(&input));
+            assert!(
+                matches!(err, Err(DatadogError::InvalidInput(_))),
+                "{}: {:?}",
+                query,
+                err
+            );
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use crate::error::{DatadogError, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 use std::{env, fs};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,14 +13,76 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub app_key: Option<String>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_command: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_key_command: Option<String>,
+
     #[serde(default = "Defaults::default_site")]
     pub site: String,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+
     #[serde(default)]
     pub defaults: Defaults,
 
     #[serde(default)]
     pub network: Network,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+
+    #[serde(skip)]
+    pub profile: Option<String>,
+
+    #[serde(skip)]
+    pub sources: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    pub api_key: Option<String>,
+    pub app_key: Option<String>,
+    pub site: Option<String>,
+    pub api_url: Option<String>,
+    pub profile: Option<String>,
+}
+
+/// Which config file `config set`/`get`/`unset` operate on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigScope {
+    Global,
+    Project,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_key: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_command: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_key_command: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub site: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+
+    /// Raw `[profiles.<name>.defaults]` table, overlaid key by key like a file layer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<toml::Table>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<toml::Table>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +101,22 @@ pub struct Defaults {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tag_filter: Option<String>,
+
+    /// Per-command tables such as `[defaults.logs]`, keyed by command name
+    #[serde(flatten, skip_serializing_if = "BTreeMap::is_empty")]
+    pub commands: BTreeMap<String, CommandDefaults>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandDefaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_range: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<i32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,9 +133,15 @@ impl Default for Config {
         Self {
             api_key: None,
             app_key: None,
+            api_key_command: None,
+            app_key_command: None,
             site: Defaults::default_site(),
+            api_url: None,
             defaults: Defaults::default(),
             network: Network::default(),
+            profiles: BTreeMap::new(),
+            profile: None,
+            sources: BTreeMap::new(),
         }
     }
 }
@@ -90,6 +176,7 @@ impl Default for Defaults {
             limit: Self::default_limit(),
             page_size: Self::default_page_size(),
             tag_filter: None,
+            commands: BTreeMap::new(),
         }
     }
 }
@@ -114,40 +201,69 @@ impl Default for Network {
 }
 
 impl Config {
-    pub fn load(
-        cli_api_key: Option<String>,
-        cli_app_key: Option<String>,
-        cli_site: Option<String>,
-    ) -> Result<Self> {
+    pub fn load(overrides: ConfigOverrides) -> Result<Self> {
         let mut config = Self::default();
+        config = config.merge(Self::load_from_file()?);
 
-        if let Ok(file_config) = Self::load_from_file() {
-            config = config.merge(file_config);
+        if let Some(name) = overrides
+            .profile
+            .clone()
+            .or_else(|| env::var("DD_PROFILE").ok())
+        {
+            config = config.apply_profile(&name)?;
         }
 
         if let Ok(key) = env::var("DD_API_KEY") {
+            config.set_source("api_key", "env DD_API_KEY");
             config.api_key = Some(key);
         }
         if let Ok(key) = env::var("DD_APP_KEY") {
+            config.set_source("app_key", "env DD_APP_KEY");
             config.app_key = Some(key);
         }
         if let Ok(site) = env::var("DD_SITE") {
+            config.set_source("site", "env DD_SITE");
             config.site = site;
         }
+        if let Ok(url) = env::var("DD_API_URL") {
+            config.set_source("api_url", "env DD_API_URL");
+            config.api_url = Some(url);
+        }
         if let Ok(filter) = env::var("DD_TAG_FILTER") {
+            config.set_source("defaults.tag_filter", "env DD_TAG_FILTER");
             config.defaults.tag_filter = Some(filter);
         }
+        if let Ok(range) = env::var("DD_TIME_RANGE") {
+            config.set_source("defaults.time_range", "env DD_TIME_RANGE");
+            config.defaults.time_range = range;
+        }
+        if let Some(limit) = env_i32("DD_LIMIT")? {
+            config.set_source("defaults.limit", "env DD_LIMIT");
+            config.defaults.limit = limit;
+        }
+        if let Some(size) = env_i32("DD_PAGE_SIZE")? {
+            config.set_source("defaults.page_size", "env DD_PAGE_SIZE");
+            config.defaults.page_size = size;
+        }
 
-        if let Some(key) = cli_api_key {
+        if let Some(key) = overrides.api_key {
+            config.set_source("api_key", &cli_source("--api-key", "DD_API_KEY", &key));
             config.api_key = Some(key);
         }
-        if let Some(key) = cli_app_key {
+        if let Some(key) = overrides.app_key {
+            config.set_source("app_key", &cli_source("--app-key", "DD_APP_KEY", &key));
             config.app_key = Some(key);
         }
-        if let Some(site) = cli_site {
+        if let Some(site) = overrides.site {
+            config.set_source("site", &cli_source("--site", "DD_SITE", &site));
             config.site = site;
         }
+        if let Some(url) = overrides.api_url {
+            config.set_source("api_url", &cli_source("--api-url", "DD_API_URL", &url));
+            config.api_url = Some(url);
+        }
 
+        config.resolve_key_commands()?;
         config.validate()?;
         Ok(config)
     }
@@ -156,37 +272,71 @@ impl Config {
         Self::load_from_file().unwrap_or_default()
     }
 
+    // Layers are applied global first, then every `.datadog.toml` from the filesystem
+    // root down to the current directory, so the nearest project file wins per key.
     fn load_from_file() -> Result<Self> {
-        let path = Self::find_project_config()
-            .or_else(Self::global_config_path)
-            .ok_or_else(|| DatadogError::InvalidInput("Cannot determine config path".into()))?;
+        let mut merged = toml::Table::new();
+        let mut sources = BTreeMap::new();
+
+        for path in Self::config_layers() {
+            #[cfg(unix)]
+            Self::check_permissions(&path)?;
+
+            let content = fs::read_to_string(&path)?;
+            let table: toml::Table = toml::from_str(&content).map_err(|e| {
+                DatadogError::InvalidInput(format!("Invalid TOML in {}: {}", path.display(), e))
+            })?;
+
+            for (key, command) in [
+                ("api_key", "api_key_command"),
+                ("app_key", "app_key_command"),
+            ] {
+                if table.contains_key(key) || table.contains_key(command) {
+                    merged.remove(key);
+                    merged.remove(command);
+                }
+            }
 
-        if !path.exists() {
-            return Err(DatadogError::InvalidInput(format!(
-                "Config not found: {}\nRun: datadog-cli config init",
-                path.display()
-            )));
+            check_defaults_keys(&table, &path)?;
+            record_sources(&table, "", &path.display().to_string(), &mut sources);
+            merge_tables(&mut merged, table);
         }
 
-        #[cfg(unix)]
-        Self::check_permissions(&path)?;
-
-        let content = fs::read_to_string(&path)?;
-        let config: Config = toml::from_str(&content)
-            .map_err(|e| DatadogError::InvalidInput(format!("Invalid TOML: {}", e)))?;
+        let mut config: Config = toml::Value::Table(merged)
+            .try_into()
+            .map_err(|e| DatadogError::InvalidInput(format!("Invalid config: {}", e)))?;
+        config.sources = sources;
 
         Ok(config)
     }
 
-    fn find_project_config() -> Option<PathBuf> {
-        let mut current = env::current_dir().ok()?;
-        loop {
-            let candidate = current.join(".datadog.toml");
-            if candidate.exists() {
-                return Some(candidate);
+    pub fn config_layers() -> Vec<PathBuf> {
+        let mut layers: Vec<PathBuf> = Self::global_config_path()
+            .filter(|p| p.exists())
+            .into_iter()
+            .collect();
+
+        for path in Self::find_project_configs() {
+            if !layers.contains(&path) {
+                layers.push(path);
             }
-            current = current.parent()?.to_path_buf();
         }
+
+        layers
+    }
+
+    fn find_project_configs() -> Vec<PathBuf> {
+        let Ok(current) = env::current_dir() else {
+            return Vec::new();
+        };
+
+        let mut found: Vec<PathBuf> = current
+            .ancestors()
+            .map(|dir| dir.join(".datadog.toml"))
+            .filter(|candidate| candidate.exists())
+            .collect();
+        found.reverse();
+        found
     }
 
     pub fn global_config_path() -> Option<PathBuf> {
@@ -218,25 +368,168 @@ impl Config {
         if other.app_key.is_some() {
             self.app_key = other.app_key;
         }
+        if other.api_key_command.is_some() {
+            self.api_key_command = other.api_key_command;
+        }
+        if other.app_key_command.is_some() {
+            self.app_key_command = other.app_key_command;
+        }
         if !other.site.is_empty() {
             self.site = other.site;
         }
+        if other.api_url.is_some() {
+            self.api_url = other.api_url;
+        }
         self.defaults = self.defaults.merge(other.defaults);
-        self.network = other.network;
+        self.network = self.network.merge(other.network);
+        self.profiles.extend(other.profiles);
+        self.sources.extend(other.sources);
         self
     }
 
+    fn set_source(&mut self, key: &str, source: &str) {
+        self.sources.insert(key.to_string(), source.to_string());
+    }
+
+    pub fn source(&self, key: &str) -> &str {
+        self.sources
+            .get(key)
+            .map(String::as_str)
+            .unwrap_or("default")
+    }
+
+    /// `[defaults.<command>]` applies unless the global default came from an env var
+    fn command_defaults(&self, command: &str, key: &str) -> Option<&CommandDefaults> {
+        if self
+            .source(&format!("defaults.{}", key))
+            .starts_with("env ")
+        {
+            return None;
+        }
+        self.defaults.commands.get(command)
+    }
+
+    pub fn time_range(&self, command: &str) -> &str {
+        self.command_defaults(command, "time_range")
+            .and_then(|d| d.time_range.as_deref())
+            .unwrap_or(&self.defaults.time_range)
+    }
+
+    pub fn limit(&self, command: &str) -> i32 {
+        self.command_defaults(command, "limit")
+            .and_then(|d| d.limit)
+            .unwrap_or(self.defaults.limit)
+    }
+
+    pub fn page_size(&self, command: &str) -> i32 {
+        self.command_defaults(command, "page_size")
+            .and_then(|d| d.page_size)
+            .unwrap_or(self.defaults.page_size)
+    }
+
+    // A key given directly (file, env or flag) always wins; the helper only runs when it is missing.
+    fn resolve_key_commands(&mut self) -> Result<()> {
+        if self.api_key.is_none()
+            && let Some(ref command) = self.api_key_command
+        {
+            self.api_key = Some(run_key_command("api_key_command", command)?);
+            self.set_source("api_key", "api_key_command");
+        }
+        if self.app_key.is_none()
+            && let Some(ref command) = self.app_key_command
+        {
+            self.app_key = Some(run_key_command("app_key_command", command)?);
+            self.set_source("app_key", "app_key_command");
+        }
+        Ok(())
+    }
+
+    fn display_key(&self, key: &str, value: &str) -> String {
+        match self.source(key) {
+            source if source.ends_with("_command") => format!("(from {})", source),
+            _ => mask_token(value),
+        }
+    }
+
+    fn apply_profile(mut self, name: &str) -> Result<Self> {
+        let profile = self.profiles.get(name).cloned().ok_or_else(|| {
+            let available: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            DatadogError::InvalidInput(format!(
+                "Unknown profile: {} (available: {})",
+                name,
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            ))
+        })?;
+
+        // Only [defaults] and [network] carry over; a profile never borrows another org's keys
+        self.api_key = profile.api_key;
+        self.api_key_command = profile.api_key_command;
+        self.app_key = profile.app_key;
+        self.app_key_command = profile.app_key_command;
+        self.site = profile.site.unwrap_or_else(Defaults::default_site);
+        self.api_url = profile.api_url;
+        for key in PROFILE_KEYS {
+            self.sources.remove(*key);
+        }
+        if let Some(defaults) = profile.defaults {
+            self.defaults = overlay_table(&self.defaults, defaults, name, "defaults")?;
+        }
+        if let Some(network) = profile.network {
+            self.network = overlay_table(&self.network, network, name, "network")?;
+        }
+
+        let prefix = format!("profiles.{}.", name);
+        let profile_keys: Vec<String> = self
+            .sources
+            .keys()
+            .filter_map(|k| k.strip_prefix(&prefix).map(str::to_string))
+            .collect();
+        for key in profile_keys {
+            self.set_source(&key, &format!("profile {}", name));
+        }
+
+        self.profile = Some(name.to_string());
+        Ok(self)
+    }
+
     fn validate(&self) -> Result<()> {
-        if self.api_key.is_none() || self.api_key.as_ref().is_some_and(|k| k.is_empty()) {
-            return Err(DatadogError::AuthError(
-                "api_key required. Use --api-key, DD_API_KEY env, or config file".into(),
-            ));
+        for (key, value, flag, env_name) in [
+            ("api_key", &self.api_key, "--api-key", "DD_API_KEY"),
+            ("app_key", &self.app_key, "--app-key", "DD_APP_KEY"),
+        ] {
+            if value.as_ref().is_some_and(|k| !k.is_empty()) {
+                continue;
+            }
+            return Err(DatadogError::CredentialError(match self.profile {
+                Some(ref name) => format!(
+                    "{key} required for profile {name}. Set {key} or {key}_command under [profiles.{name}] \
+                     (profiles do not inherit top-level keys), or use {flag} / {env_name} env"
+                ),
+                None => format!(
+                    "{key} required. Use {flag}, {env_name} env, or {key}/{key}_command in config file"
+                ),
+            }));
         }
 
-        if self.app_key.is_none() || self.app_key.as_ref().is_some_and(|k| k.is_empty()) {
-            return Err(DatadogError::AuthError(
-                "app_key required. Use --app-key, DD_APP_KEY env, or config file".into(),
-            ));
+        if let Some(command) = self
+            .defaults
+            .commands
+            .keys()
+            .find(|c| !COMMAND_NAMES.contains(&c.as_str()))
+        {
+            return Err(DatadogError::InvalidInput(format!(
+                "Unknown command in [defaults.{}] (expected one of: {})",
+                command,
+                COMMAND_NAMES.join(", ")
+            )));
+        }
+
+        if let Some(ref url) = self.api_url {
+            return validate_api_url(url);
         }
 
         if !self.site.contains("datadoghq.") && !self.site.contains("ddog-gov.") {
@@ -249,6 +542,13 @@ impl Config {
         Ok(())
     }
 
+    pub fn base_url(&self) -> String {
+        match self.api_url {
+            Some(ref url) => url.trim_end_matches('/').to_string(),
+            None => format!("https://api.{}", self.site),
+        }
+    }
+
     pub fn init() -> Result<PathBuf> {
         let path = Self::global_config_path()
             .ok_or_else(|| DatadogError::InvalidInput("Cannot determine config path".into()))?;
@@ -267,6 +567,11 @@ impl Config {
         let template = r#"api_key = "your-api-key-here"
 app_key = "your-app-key-here"
 site = "datadoghq.com"
+# api_url = "http://localhost:8080"
+
+# Read keys from a credential helper instead of storing them here
+# api_key_command = "pass show datadog/api-key"
+# app_key_command = "op read op://vault/datadog/app-key"
 
 [defaults]
 format = "json"
@@ -275,38 +580,140 @@ limit = 10
 page_size = 100
 # tag_filter = "env:,service:"
 
+# Per-command overrides of time_range, limit and page_size
+# [defaults.logs]
+# limit = 50
+
 [network]
 timeout_secs = 30
 max_retries = 3
+
+# Named profiles inherit only [defaults] and [network] and need their own keys;
+# select with --profile or DD_PROFILE
+# [profiles.staging]
+# api_key = "staging-api-key"
+# app_key = "staging-app-key"
+# site = "datadoghq.eu"
 "#;
 
         fs::write(&path, template)?;
+        set_private_permissions(&path)?;
 
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&path)?.permissions();
-            perms.set_mode(0o600);
-            fs::set_permissions(&path, perms)?;
+        Ok(path)
+    }
+
+    pub fn scope_path(scope: ConfigScope) -> Result<PathBuf> {
+        match scope {
+            ConfigScope::Global => Self::global_config_path()
+                .ok_or_else(|| DatadogError::InvalidInput("Cannot determine config path".into())),
+            ConfigScope::Project => match Self::find_project_configs().pop() {
+                Some(path) => Ok(path),
+                None => Ok(env::current_dir()?.join(".datadog.toml")),
+            },
         }
+    }
+
+    /// Read a dotted key from one config file, or from the merged layers when no scope is given
+    pub fn get_value(key: &str, scope: Option<ConfigScope>) -> Result<String> {
+        validate_key(key)?;
+
+        let root = match scope {
+            Some(scope) => {
+                let path = Self::scope_path(scope)?;
+                if !path.exists() {
+                    return Err(DatadogError::InvalidInput(format!(
+                        "Config not found: {}",
+                        path.display()
+                    )));
+                }
+                toml::Value::Table(toml::from_str(&fs::read_to_string(&path)?).map_err(|e| {
+                    DatadogError::InvalidInput(format!("Invalid TOML in {}: {}", path.display(), e))
+                })?)
+            }
+            None => toml::Value::try_from(Self::load_from_file()?)
+                .map_err(|e| DatadogError::InvalidInput(format!("Invalid config: {}", e)))?,
+        };
+
+        let value = key
+            .split('.')
+            .try_fold(&root, |value, part| value.get(part))
+            .ok_or_else(|| DatadogError::InvalidInput(format!("{} is not set", key)))?;
+
+        Ok(match value {
+            toml::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    }
+
+    /// Write a dotted key into a config file, keeping its comments and layout
+    pub fn set_value(key: &str, value: &str, scope: ConfigScope) -> Result<PathBuf> {
+        let path = Self::scope_path(scope)?;
+        let content = if path.exists() {
+            fs::read_to_string(&path)?
+        } else {
+            String::new()
+        };
+
+        let updated = set_in_document(&content, key, value)?;
+        Self::write_config_file(&path, &updated)?;
 
         Ok(path)
     }
 
-    pub fn show() -> Result<String> {
-        let config = Self::load(None, None, None)?;
-        let path = Self::global_config_path()
+    /// Remove a dotted key from a config file; returns whether it was set
+    pub fn unset_value(key: &str, scope: ConfigScope) -> Result<(PathBuf, bool)> {
+        validate_key(key)?;
+
+        let path = Self::scope_path(scope)?;
+        if !path.exists() {
+            return Ok((path, false));
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let (updated, removed) = unset_in_document(&content, key)?;
+        if removed {
+            Self::write_config_file(&path, &updated)?;
+        }
+
+        Ok((path, removed))
+    }
+
+    fn write_config_file(path: &Path, content: &str) -> Result<()> {
+        let is_new = !path.exists();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, content)?;
+        if is_new {
+            set_private_permissions(path)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn show(overrides: ConfigOverrides, with_sources: bool) -> Result<String> {
+        let config = Self::load(overrides)?;
+        let layers: Vec<String> = Self::config_layers()
+            .iter()
             .map(|p| p.display().to_string())
-            .unwrap_or_else(|| "unknown".to_string());
+            .collect();
+        let path = if layers.is_empty() {
+            "(none)".to_string()
+        } else {
+            layers.join(", ")
+        };
 
         let api_key = config.api_key.as_ref().unwrap();
         let app_key = config.app_key.as_ref().unwrap();
 
-        Ok(format!(
+        let mut output = format!(
             "Config: {}\n\
+             Profile: {}\n\
              API Key: {}\n\
              App Key: {}\n\
-             Site: {}\n\n\
+             Site: {}\n\
+             API URL: {}\n\n\
              [defaults]\n\
              format: {}\n\
              time_range: {}\n\
@@ -317,9 +724,11 @@ max_retries = 3
              timeout_secs: {}\n\
              max_retries: {}",
             path,
-            mask_token(api_key),
-            mask_token(app_key),
+            config.profile.as_deref().unwrap_or("(default)"),
+            config.display_key("api_key", api_key),
+            config.display_key("app_key", app_key),
             config.site,
+            config.base_url(),
             config.defaults.format,
             config.defaults.time_range,
             config.defaults.limit,
@@ -327,7 +736,65 @@ max_retries = 3
             config.defaults.tag_filter.as_deref().unwrap_or("(none)"),
             config.network.timeout_secs,
             config.network.max_retries,
-        ))
+        );
+
+        for (command, defaults) in &config.defaults.commands {
+            output.push_str(&format!("\n\n[defaults.{}]", command));
+            if let Some(ref range) = defaults.time_range {
+                output.push_str(&format!("\ntime_range: {}", range));
+            }
+            if let Some(limit) = defaults.limit {
+                output.push_str(&format!("\nlimit: {}", limit));
+            }
+            if let Some(size) = defaults.page_size {
+                output.push_str(&format!("\npage_size: {}", size));
+            }
+        }
+
+        for (name, profile) in &config.profiles {
+            let active = if config.profile.as_deref() == Some(name.as_str()) {
+                " (active)"
+            } else {
+                ""
+            };
+            let masked = |key: &Option<String>, command: &Option<String>| match (key, command) {
+                (Some(k), _) => mask_token(k),
+                (None, Some(_)) => "(command)".to_string(),
+                (None, None) => "(not set)".to_string(),
+            };
+
+            output.push_str(&format!(
+                "\n\n[profiles.{}]{}\n\
+                 api_key: {}\n\
+                 app_key: {}\n\
+                 site: {}",
+                name,
+                active,
+                masked(&profile.api_key, &profile.api_key_command),
+                masked(&profile.app_key, &profile.app_key_command),
+                profile.site.as_deref().unwrap_or("(default)"),
+            ));
+            if let Some(ref url) = profile.api_url {
+                output.push_str(&format!("\napi_url: {}", url));
+            }
+        }
+
+        if with_sources {
+            output.push_str("\n\n[sources]");
+            for key in SOURCE_KEYS {
+                output.push_str(&format!("\n{}: {}", key, config.source(key)));
+            }
+            for command in config.defaults.commands.keys() {
+                for field in COMMAND_DEFAULT_KEYS {
+                    let key = format!("defaults.{}.{}", command, field);
+                    if let Some(source) = config.sources.get(&key) {
+                        output.push_str(&format!("\n{}: {}", key, source));
+                    }
+                }
+            }
+        }
+
+        Ok(output)
     }
 
     pub fn edit() -> Result<()> {
@@ -383,10 +850,428 @@ impl Defaults {
         if other.tag_filter.is_some() {
             self.tag_filter = other.tag_filter;
         }
+        for (command, overrides) in other.commands {
+            let entry = self.commands.remove(&command).unwrap_or_default();
+            self.commands.insert(command, entry.merge(overrides));
+        }
+        self
+    }
+}
+
+impl CommandDefaults {
+    fn merge(mut self, other: Self) -> Self {
+        if other.time_range.is_some() {
+            self.time_range = other.time_range;
+        }
+        if other.limit.is_some() {
+            self.limit = other.limit;
+        }
+        if other.page_size.is_some() {
+            self.page_size = other.page_size;
+        }
         self
     }
 }
 
+impl Network {
+    fn merge(mut self, other: Self) -> Self {
+        if other.timeout_secs != Self::default_timeout_secs() {
+            self.timeout_secs = other.timeout_secs;
+        }
+        if other.max_retries != Self::default_max_retries() {
+            self.max_retries = other.max_retries;
+        }
+        self
+    }
+}
+
+const SOURCE_KEYS: &[&str] = &[
+    "api_key",
+    "app_key",
+    "api_key_command",
+    "app_key_command",
+    "site",
+    "api_url",
+    "defaults.format",
+    "defaults.time_range",
+    "defaults.limit",
+    "defaults.page_size",
+    "defaults.tag_filter",
+    "network.timeout_secs",
+    "network.max_retries",
+];
+
+/// Connection settings a profile replaces rather than inherits
+const PROFILE_KEYS: &[&str] = &[
+    "api_key",
+    "app_key",
+    "api_key_command",
+    "app_key_command",
+    "site",
+    "api_url",
+];
+
+const INTEGER_KEYS: &[&str] = &[
+    "defaults.limit",
+    "defaults.page_size",
+    "network.timeout_secs",
+    "network.max_retries",
+];
+
+const OUTPUT_FORMATS: &[&str] = &["json", "jsonl", "table", "chart", "csv", "tsv"];
+
+/// Commands that accept a `[defaults.<command>]` table
+const COMMAND_NAMES: &[&str] = &[
+    "metrics",
+    "logs",
+    "monitors",
+    "events",
+    "hosts",
+    "dashboards",
+    "spans",
+    "services",
+    "rum",
+];
+
+const COMMAND_DEFAULT_KEYS: &[&str] = &["time_range", "limit", "page_size"];
+
+/// Check a dotted key names a setting, returning its path relative to any profile table
+fn validate_key(key: &str) -> Result<&str> {
+    let setting = match key.strip_prefix("profiles.") {
+        Some(rest) => rest
+            .split_once('.')
+            .map(|(_, field)| field)
+            .unwrap_or_default(),
+        None => key,
+    };
+
+    if let Some((command, field)) = setting
+        .strip_prefix("defaults.")
+        .and_then(|rest| rest.split_once('.'))
+        && COMMAND_NAMES.contains(&command)
+        && let Some(field) = COMMAND_DEFAULT_KEYS.iter().find(|k| **k == field)
+    {
+        // `defaults.logs.limit` takes the same values as `defaults.limit`
+        return Ok(match *field {
+            "limit" => "defaults.limit",
+            "page_size" => "defaults.page_size",
+            _ => "defaults.time_range",
+        });
+    }
+
+    if SOURCE_KEYS.contains(&setting) {
+        Ok(setting)
+    } else {
+        Err(DatadogError::InvalidInput(format!(
+            "Unknown config key: {} (valid keys: {}, defaults.<command>.<key>, or profiles.<name>.<key>)",
+            key,
+            SOURCE_KEYS.join(", ")
+        )))
+    }
+}
+
+/// Reject unknown keys in `[defaults]` and `[profiles.<name>.defaults]` of one config file
+///
+/// Any table under `[defaults]` is read as per-command defaults, so without this
+/// a misspelled scalar such as `fromat = "table"` fails with an opaque type error.
+fn check_defaults_keys(table: &toml::Table, path: &Path) -> Result<()> {
+    let profiles = table
+        .get("profiles")
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, profile)| Some((format!("profiles.{}.", name), profile.as_table()?)));
+
+    for (prefix, section) in std::iter::once((String::new(), table)).chain(profiles) {
+        let Some(defaults) = section.get("defaults").and_then(toml::Value::as_table) else {
+            continue;
+        };
+
+        for (key, value) in defaults {
+            let unknown = match value.as_table() {
+                Some(command) => command
+                    .keys()
+                    .find(|field| !COMMAND_DEFAULT_KEYS.contains(&field.as_str()))
+                    .map(|field| {
+                        (
+                            format!("{}defaults.{}.{}", prefix, key, field),
+                            COMMAND_DEFAULT_KEYS.join(", "),
+                        )
+                    }),
+                None if SOURCE_KEYS.contains(&format!("defaults.{}", key).as_str()) => None,
+                None => Some((
+                    format!("{}defaults.{}", prefix, key),
+                    format!(
+                        "{} or a [defaults.<command>] table",
+                        SOURCE_KEYS
+                            .iter()
+                            .filter_map(|k| k.strip_prefix("defaults."))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                )),
+            };
+
+            if let Some((key, expected)) = unknown {
+                return Err(DatadogError::InvalidInput(format!(
+                    "Unknown config key {} in {} (expected {})",
+                    key,
+                    path.display(),
+                    expected
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_setting(key: &str, setting: &str, value: &str) -> Result<toml_edit::Value> {
+    if INTEGER_KEYS.contains(&setting) {
+        return value
+            .trim()
+            .parse::<i64>()
+            .map(toml_edit::Value::from)
+            .map_err(|_| {
+                DatadogError::InvalidInput(format!("{} expects an integer, got '{}'", key, value))
+            });
+    }
+
+    match setting {
+        "defaults.format" if !OUTPUT_FORMATS.contains(&value) => {
+            return Err(DatadogError::InvalidInput(format!(
+                "{} must be one of: {}",
+                key,
+                OUTPUT_FORMATS.join(", ")
+            )));
+        }
+        "api_url" => validate_api_url(value)?,
+        _ => {}
+    }
+
+    Ok(toml_edit::Value::from(value))
+}
+
+fn parse_document(content: &str) -> Result<toml_edit::DocumentMut> {
+    content
+        .parse()
+        .map_err(|e| DatadogError::InvalidInput(format!("Invalid TOML: {}", e)))
+}
+
+fn set_in_document(content: &str, key: &str, value: &str) -> Result<String> {
+    let setting = validate_key(key)?;
+    let value = parse_setting(key, setting, value)?;
+    let mut doc = parse_document(content)?;
+
+    let parts: Vec<&str> = key.split('.').collect();
+    let (leaf, parents) = parts.split_last().unwrap_or((&key, &[]));
+
+    let mut table: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    for (i, part) in parents.iter().enumerate() {
+        if table.get(part).is_none() {
+            // Only the innermost table gets a `[header]`; `profiles` stays implicit
+            let mut new_table = toml_edit::Table::new();
+            new_table.set_implicit(i + 1 < parents.len());
+            table.insert(part, toml_edit::Item::Table(new_table));
+        }
+        table = table
+            .get_mut(part)
+            .and_then(toml_edit::Item::as_table_like_mut)
+            .ok_or_else(|| DatadogError::InvalidInput(format!("{} is not a table", part)))?;
+    }
+    match table.get_mut(leaf).and_then(toml_edit::Item::as_value_mut) {
+        // Assign in place so the key keeps its surrounding comments
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = value;
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            table.insert(leaf, toml_edit::Item::Value(value));
+        }
+    }
+
+    let updated = doc.to_string();
+    toml::from_str::<Config>(&updated)
+        .map_err(|e| DatadogError::InvalidInput(format!("Invalid value for {}: {}", key, e)))?;
+
+    Ok(updated)
+}
+
+fn unset_in_document(content: &str, key: &str) -> Result<(String, bool)> {
+    let mut doc = parse_document(content)?;
+
+    let parts: Vec<&str> = key.split('.').collect();
+    let (leaf, parents) = parts.split_last().unwrap_or((&key, &[]));
+
+    let mut table: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    for part in parents {
+        match table
+            .get_mut(part)
+            .and_then(toml_edit::Item::as_table_like_mut)
+        {
+            Some(nested) => table = nested,
+            None => return Ok((content.to_string(), false)),
+        }
+    }
+    let removed = table.remove(leaf).is_some();
+
+    Ok((doc.to_string(), removed))
+}
+
+fn env_i32(name: &str) -> Result<Option<i32>> {
+    match env::var(name) {
+        Ok(value) => value.trim().parse().map(Some).map_err(|_| {
+            DatadogError::InvalidInput(format!("{} must be an integer, got '{}'", name, value))
+        }),
+        Err(_) => Ok(None),
+    }
+}
+
+fn set_private_permissions(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(path)?.permissions();
+        perms.set_mode(0o600);
+        fs::set_permissions(path, perms)?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Apply a profile's raw table over the resolved section so explicitly set keys always win
+fn overlay_table<T>(base: &T, overlay: toml::Table, profile: &str, section: &str) -> Result<T>
+where
+    T: Serialize + serde::de::DeserializeOwned,
+{
+    let invalid = |e: &dyn std::fmt::Display| {
+        DatadogError::InvalidInput(format!("Invalid [profiles.{}.{}]: {}", profile, section, e))
+    };
+
+    let mut merged = toml::Table::try_from(base).map_err(|e| invalid(&e))?;
+    merge_tables(&mut merged, overlay);
+    toml::Value::Table(merged)
+        .try_into()
+        .map_err(|e| invalid(&e))
+}
+
+fn record_sources(
+    table: &toml::Table,
+    prefix: &str,
+    source: &str,
+    sources: &mut BTreeMap<String, String>,
+) {
+    for (key, value) in table {
+        let path = format!("{}{}", prefix, key);
+        match value {
+            toml::Value::Table(nested) => {
+                record_sources(nested, &format!("{}.", path), source, sources)
+            }
+            _ => {
+                sources.insert(path, source.to_string());
+            }
+        }
+    }
+}
+
+// Helpers like `pass` or `op` may prompt or hit the network, so each command runs once per process.
+fn run_key_command(setting: &str, command: &str) -> Result<String> {
+    static CACHE: OnceLock<Mutex<HashMap<String, String>>> = OnceLock::new();
+
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(secret) = cache.lock().ok().and_then(|c| c.get(command).cloned()) {
+        return Ok(secret);
+    }
+
+    #[cfg(unix)]
+    let output = std::process::Command::new("sh")
+        .args(["-c", command])
+        .output();
+    #[cfg(windows)]
+    let output = std::process::Command::new("cmd")
+        .args(["/C", command])
+        .output();
+
+    let output = output
+        .map_err(|e| DatadogError::CredentialError(format!("Failed to run {}: {}", setting, e)))?;
+
+    if !output.status.success() {
+        return Err(DatadogError::CredentialError(format!(
+            "{} exited with {}: {}",
+            setting,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let secret = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if secret.is_empty() {
+        return Err(DatadogError::CredentialError(format!(
+            "{} produced no output",
+            setting
+        )));
+    }
+
+    if let Ok(mut c) = cache.lock() {
+        c.insert(command.to_string(), secret.clone());
+    }
+    Ok(secret)
+}
+
+// clap fills flags from their env var, so a flag value equal to the env var came from the env
+fn cli_source(flag: &str, env_name: &str, value: &str) -> String {
+    if env::var(env_name).is_ok_and(|v| v == value) {
+        format!("env {}", env_name)
+    } else {
+        format!("flag {}", flag)
+    }
+}
+
+fn validate_api_url(url: &str) -> Result<()> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| DatadogError::InvalidInput(format!("Invalid api_url '{}': {}", url, e)))?;
+
+    match parsed.scheme() {
+        "https" => Ok(()),
+        "http" if is_loopback_host(parsed.host_str().unwrap_or_default()) => Ok(()),
+        "http" => Err(DatadogError::InvalidInput(format!(
+            "Invalid api_url '{}': http is only allowed for loopback addresses",
+            url
+        ))),
+        scheme => Err(DatadogError::InvalidInput(format!(
+            "Invalid api_url '{}': unsupported scheme '{}'",
+            url, scheme
+        ))),
+    }
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    if host.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<std::net::IpAddr>()
+        .is_ok_and(|ip| ip.is_loopback())
+}
+
 fn mask_token(token: &str) -> String {
     if token.len() > 8 {
         format!("{}...{}", &token[..4], &token[token.len() - 4..])
@@ -416,9 +1301,15 @@ mod tests {
         let config = Config {
             api_key: Some("test123".to_string()),
             app_key: Some("app456".to_string()),
+            api_key_command: None,
+            app_key_command: None,
             site: "datadoghq.com".to_string(),
+            api_url: None,
             defaults: Defaults::default(),
             network: Network::default(),
+            profiles: BTreeMap::new(),
+            profile: None,
+            sources: BTreeMap::new(),
         };
         assert!(config.validate().is_ok());
     }
@@ -444,6 +1335,166 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_validate_api_url_bypasses_site() {
+        let config = Config {
+            api_key: Some("test123".to_string()),
+            app_key: Some("app456".to_string()),
+            site: "invalid.com".to_string(),
+            api_url: Some("https://dd-proxy.internal".to_string()),
+            ..Config::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.base_url(), "https://dd-proxy.internal");
+    }
+
+    #[test]
+    fn test_validate_api_url_http_loopback_only() {
+        assert!(validate_api_url("http://localhost:8080").is_ok());
+        assert!(validate_api_url("http://127.0.0.1:8126/").is_ok());
+        assert!(validate_api_url("http://[::1]:9000").is_ok());
+        assert!(validate_api_url("http://example.com").is_err());
+        assert!(validate_api_url("ftp://localhost").is_err());
+        assert!(validate_api_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_base_url() {
+        let config = Config::default();
+        assert_eq!(config.base_url(), "https://api.datadoghq.com");
+
+        let config = Config {
+            api_url: Some("http://localhost:8080/".to_string()),
+            ..Config::default()
+        };
+        assert_eq!(config.base_url(), "http://localhost:8080");
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let config: Config = toml::from_str(
+            r#"
+            api_key = "prod-api-key"
+            app_key = "prod-app-key"
+
+            [defaults]
+            format = "table"
+
+            [network]
+            timeout_secs = 60
+
+            [profiles.staging]
+            api_key = "staging-api-key"
+            site = "datadoghq.eu"
+            "#,
+        )
+        .unwrap();
+
+        let staging = config.clone().apply_profile("staging").unwrap();
+        assert_eq!(staging.profile.as_deref(), Some("staging"));
+        assert_eq!(staging.api_key.as_deref(), Some("staging-api-key"));
+        assert_eq!(staging.app_key, None);
+        assert_eq!(staging.site, "datadoghq.eu");
+        assert_eq!(staging.defaults.format, "table");
+        assert_eq!(staging.network.timeout_secs, 60);
+
+        let err = staging.validate().unwrap_err();
+        assert!(matches!(err, DatadogError::CredentialError(_)));
+        assert!(
+            err.to_string()
+                .contains("app_key required for profile staging")
+        );
+        assert!(err.to_string().contains("[profiles.staging]"));
+    }
+
+    #[test]
+    fn test_apply_unknown_profile() {
+        let mut config = Config::default();
+        config
+            .profiles
+            .insert("prod".to_string(), Profile::default());
+
+        let err = config.apply_profile("staging").unwrap_err();
+        assert!(err.to_string().contains("Unknown profile: staging"));
+        assert!(err.to_string().contains("prod"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_key_commands() {
+        let mut config = Config {
+            api_key_command: Some("echo ' api-secret-from-helper '".to_string()),
+            app_key: Some("plain-app-key".to_string()),
+            app_key_command: Some("exit 1".to_string()),
+            ..Config::default()
+        };
+
+        config.resolve_key_commands().unwrap();
+        assert_eq!(config.api_key(), "api-secret-from-helper");
+        assert_eq!(config.app_key(), "plain-app-key");
+        assert_eq!(config.source("api_key"), "api_key_command");
+        assert_eq!(
+            config.display_key("api_key", config.api_key()),
+            "(from api_key_command)"
+        );
+        assert_eq!(
+            config.display_key("app_key", config.app_key()),
+            "plai...-key"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_key_command_failure() {
+        let err = run_key_command("api_key_command", "echo oops >&2; exit 3").unwrap_err();
+        assert!(matches!(err, DatadogError::CredentialError(_)));
+        assert!(err.to_string().contains("oops"));
+
+        let err = run_key_command("api_key_command", "true").unwrap_err();
+        assert!(err.to_string().contains("produced no output"));
+    }
+
+    #[test]
+    fn test_profile_key_command_replaces_key() {
+        let config: Config = toml::from_str(
+            r#"
+            api_key = "prod-api-key"
+            [profiles.vault]
+            api_key_command = "vault read -field=key secret/datadog"
+            "#,
+        )
+        .unwrap();
+
+        let config = config.apply_profile("vault").unwrap();
+        assert!(config.api_key.is_none());
+        assert_eq!(
+            config.api_key_command.as_deref(),
+            Some("vault read -field=key secret/datadog")
+        );
+    }
+
+    #[test]
+    fn test_check_defaults_keys() {
+        let path = Path::new("config.toml");
+        let check = |content: &str| check_defaults_keys(&toml::from_str(content).unwrap(), path);
+
+        assert!(check("[defaults]\nformat = \"table\"\n[defaults.logs]\nlimit = 5").is_ok());
+
+        let err = check("[defaults]\nfromat = \"table\"").unwrap_err();
+        assert!(matches!(err, DatadogError::InvalidInput(_)));
+        assert!(
+            err.to_string()
+                .contains("Unknown config key defaults.fromat in config.toml")
+        );
+        assert!(err.to_string().contains("format, time_range"));
+
+        let err = check("[defaults.logs]\nlimt = 5").unwrap_err();
+        assert!(err.to_string().contains("defaults.logs.limt"));
+
+        let err = check("[profiles.staging.defaults]\ncolour = \"red\"").unwrap_err();
+        assert!(err.to_string().contains("profiles.staging.defaults.colour"));
+    }
+
     #[test]
     fn test_mask_token() {
         assert_eq!(mask_token("abcdefghijklmnop"), "abcd...mnop");
@@ -465,6 +1516,214 @@ mod tests {
         assert_eq!(merged.page_size, 100);
     }
 
+    #[test]
+    fn test_network_merge_per_field() {
+        let base = Network {
+            timeout_secs: 60,
+            max_retries: 3,
+        };
+        let other = Network {
+            max_retries: 5,
+            ..Network::default()
+        };
+
+        let merged = base.merge(other);
+        assert_eq!(merged.timeout_secs, 60);
+        assert_eq!(merged.max_retries, 5);
+    }
+
+    #[test]
+    fn test_merge_tables_layers_per_field() {
+        let global: toml::Table = toml::from_str(
+            r#"
+            api_key = "global-key"
+            [defaults]
+            format = "table"
+            limit = 50
+            [network]
+            timeout_secs = 60
+            "#,
+        )
+        .unwrap();
+        let project: toml::Table = toml::from_str(
+            r#"
+            [defaults]
+            tag_filter = "env:"
+            format = "json"
+            [network]
+            max_retries = 1
+            "#,
+        )
+        .unwrap();
+
+        let mut sources = BTreeMap::new();
+        let mut merged = toml::Table::new();
+        record_sources(&global, "", "global.toml", &mut sources);
+        merge_tables(&mut merged, global);
+        record_sources(&project, "", ".datadog.toml", &mut sources);
+        merge_tables(&mut merged, project);
+
+        let config: Config = toml::Value::Table(merged).try_into().unwrap();
+        assert_eq!(config.api_key.as_deref(), Some("global-key"));
+        assert_eq!(config.defaults.format, "json");
+        assert_eq!(config.defaults.limit, 50);
+        assert_eq!(config.defaults.tag_filter.as_deref(), Some("env:"));
+        assert_eq!(config.network.timeout_secs, 60);
+        assert_eq!(config.network.max_retries, 1);
+
+        assert_eq!(sources["api_key"], "global.toml");
+        assert_eq!(sources["defaults.format"], ".datadog.toml");
+        assert_eq!(sources["defaults.limit"], "global.toml");
+        assert_eq!(sources["network.max_retries"], ".datadog.toml");
+    }
+
+    #[test]
+    fn test_profile_sources() {
+        let mut config: Config = toml::from_str(
+            r#"
+            api_key = "prod-api-key"
+            [profiles.staging]
+            site = "datadoghq.eu"
+            "#,
+        )
+        .unwrap();
+        config.set_source("api_key", "config.toml");
+        config.set_source("defaults.format", "config.toml");
+        config.set_source("profiles.staging.site", "config.toml");
+
+        let config = config.apply_profile("staging").unwrap();
+        assert_eq!(config.source("api_key"), "default");
+        assert_eq!(config.source("defaults.format"), "config.toml");
+        assert_eq!(config.source("site"), "profile staging");
+        assert_eq!(config.source("network.timeout_secs"), "default");
+    }
+
+    #[test]
+    fn test_profile_resets_field_to_default() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [defaults]
+            format = "table"
+            limit = 500
+
+            [defaults.logs]
+            limit = 50
+
+            [network]
+            timeout_secs = 60
+
+            [profiles.ci]
+            api_key = "ci-api-key"
+            app_key = "ci-app-key"
+
+            [profiles.ci.defaults]
+            format = "json"
+            limit = 100
+
+            [profiles.ci.network]
+            timeout_secs = 30
+            "#,
+        )
+        .unwrap();
+        config.set_source("defaults.format", "config.toml");
+        config.set_source("profiles.ci.defaults.format", "config.toml");
+
+        let ci = config.apply_profile("ci").unwrap();
+        assert_eq!(ci.defaults.format, "json");
+        assert_eq!(ci.defaults.limit, 100);
+        assert_eq!(ci.limit("logs"), 50);
+        assert_eq!(ci.network.timeout_secs, 30);
+        assert_eq!(ci.source("defaults.format"), "profile ci");
+    }
+
+    #[test]
+    fn test_set_in_document_preserves_comments() {
+        let content =
+            "# my keys\napi_key = \"k\"\n\n[defaults]\n# preferred output\nformat = \"json\"\n";
+
+        let updated = set_in_document(content, "defaults.format", "table").unwrap();
+        assert!(updated.contains("# my keys"));
+        assert!(updated.contains("# preferred output"));
+        assert!(updated.contains("format = \"table\""));
+
+        let updated = set_in_document(&updated, "network.timeout_secs", "60").unwrap();
+        assert!(updated.contains("[network]\ntimeout_secs = 60"));
+
+        let updated = set_in_document(&updated, "profiles.staging.site", "datadoghq.eu").unwrap();
+        assert!(updated.contains("[profiles.staging]\nsite = \"datadoghq.eu\""));
+        assert!(!updated.contains("[profiles]\n"));
+    }
+
+    #[test]
+    fn test_set_in_document_validates() {
+        assert!(set_in_document("", "defaults.colour", "red").is_err());
+        assert!(set_in_document("", "network.timeout_secs", "soon").is_err());
+        assert!(set_in_document("", "network.max_retries", "-1").is_err());
+        assert!(set_in_document("", "defaults.format", "xml").is_err());
+        assert!(set_in_document("", "api_url", "http://example.com").is_err());
+        assert!(set_in_document("", "profiles.staging.network.max_retries", "5").is_ok());
+    }
+
+    #[test]
+    fn test_unset_in_document() {
+        let content = "[defaults]\n# keep me\nformat = \"json\"\ntag_filter = \"env:\"\n";
+
+        let (updated, removed) = unset_in_document(content, "defaults.tag_filter").unwrap();
+        assert!(removed);
+        assert!(!updated.contains("tag_filter"));
+        assert!(updated.contains("# keep me"));
+
+        let (_, removed) = unset_in_document(&updated, "network.timeout_secs").unwrap();
+        assert!(!removed);
+    }
+
+    #[test]
+    fn test_command_defaults() {
+        let file: Config = toml::from_str(
+            r#"
+            [defaults]
+            limit = 20
+
+            [defaults.logs]
+            limit = 50
+            time_range = "15 minutes ago"
+
+            [defaults.hosts]
+            page_size = 500
+            "#,
+        )
+        .unwrap();
+        let mut config = Config::default().merge(file);
+
+        assert_eq!(config.limit("logs"), 50);
+        assert_eq!(config.limit("spans"), 20);
+        assert_eq!(config.time_range("logs"), "15 minutes ago");
+        assert_eq!(config.time_range("metrics"), "1 hour ago");
+        assert_eq!(config.page_size("hosts"), 500);
+        assert_eq!(config.page_size("monitors"), 100);
+
+        config.set_source("defaults.limit", "env DD_LIMIT");
+        assert_eq!(config.limit("logs"), 20);
+    }
+
+    #[test]
+    fn test_unknown_command_defaults() {
+        let mut config = Config {
+            api_key: Some("key".into()),
+            app_key: Some("app".into()),
+            ..Config::default()
+        };
+        config
+            .defaults
+            .commands
+            .insert("logz".into(), CommandDefaults::default());
+
+        assert!(config.validate().is_err());
+        assert!(validate_key("defaults.logs.limit").is_ok());
+        assert!(validate_key("defaults.logz.limit").is_err());
+        assert!(set_in_document("", "defaults.logs.limit", "many").is_err());
+    }
+
     #[test]
     fn test_network_defaults() {
         let network = Network::default();
@@ -1,11 +1,11 @@
 use reqwest::{Client, Response, StatusCode};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use super::models::*;
 use super::retry;
-use crate::error::{DatadogError, Result};
+use crate::error::{ApiErrorResponse, DatadogError, Result};
 
 pub struct DatadogClient {
     client: Client,
@@ -43,6 +43,11 @@ impl DatadogClient {
         })
     }
 
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
     pub fn get_tag_filter(&self) -> Option<&str> {
         self.tag_filter.as_deref()
     }
@@ -56,6 +61,7 @@ impl DatadogClient {
     ) -> Result<T> {
         let url = format!("{}{}", self.base_url, endpoint);
 
+        let started = Instant::now();
         let mut retries = 0;
         loop {
             let mut request = self
@@ -75,16 +81,48 @@ impl DatadogClient {
                 request = request.json(data);
             }
 
-            let response = request.send().await?;
+            let (result, retryable, hint) = match request.send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    let hint = retry::server_retry_hint(response.headers());
+                    (
+                        self.handle_response(response).await,
+                        retry::is_retryable_status(status),
+                        hint,
+                    )
+                }
+                Err(e) => {
+                    let retryable = retry::is_retryable_error(&e);
+                    let error = if e.is_timeout() {
+                        DatadogError::TimeoutError
+                    } else {
+                        DatadogError::NetworkError(e)
+                    };
+                    (Err(error), retryable, None)
+                }
+            };
 
-            match self.handle_response(response).await {
+            match result {
                 Ok(data) => return Ok(data),
                 Err(e) => {
-                    if !retry::should_retry(retries, self.max_retries) {
+                    if !retryable || !retry::should_retry(retries, self.max_retries) {
                         return Err(e);
                     }
                     retries += 1;
-                    tokio::time::sleep(retry::calculate_backoff(retries)).await;
+                    let delay = retry::retry_delay(retries, hint);
+                    if started.elapsed() + delay > retry::MAX_RETRY_ELAPSED {
+                        return Err(e);
+                    }
+                    tracing::debug!(
+                        "Retrying {} {} in {:?} (attempt {}/{}): {}",
+                        method,
+                        endpoint,
+                        delay,
+                        retries,
+                        self.max_retries,
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
                 }
             }
         }
@@ -99,25 +137,40 @@ impl DatadogClient {
                 .await
                 .map_err(DatadogError::NetworkError)
         } else {
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
+            let path = response.url().path().to_string();
+            let rate_limit = retry::rate_limit_info(response.headers());
+            let error_text = response.text().await.unwrap_or_default();
+            let details = Box::new(ApiErrorResponse::new(
+                status.as_u16(),
+                &path,
+                &error_text,
+                rate_limit,
+            ));
 
             match status {
                 StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
-                    Err(DatadogError::AuthError(error_text))
+                    Err(DatadogError::AuthError(details))
                 }
-                StatusCode::TOO_MANY_REQUESTS => Err(DatadogError::RateLimitError),
+                StatusCode::NOT_FOUND => Err(DatadogError::NotFound(details)),
+                StatusCode::TOO_MANY_REQUESTS => Err(DatadogError::RateLimitError(details)),
                 StatusCode::REQUEST_TIMEOUT => Err(DatadogError::TimeoutError),
-                _ => Err(DatadogError::ApiError(format!(
-                    "HTTP {}: {}",
-                    status, error_text
-                ))),
+                _ => Err(DatadogError::HttpError(details)),
             }
         }
     }
 
+    // ============= Authentication API =============
+
+    pub async fn validate_api_key(&self) -> Result<ValidateResponse> {
+        self.request(reqwest::Method::GET, "/api/v1/validate", None, None::<()>)
+            .await
+    }
+
+    pub async fn get_org(&self) -> Result<OrgsResponse> {
+        self.request(reqwest::Method::GET, "/api/v1/org", None, None::<()>)
+            .await
+    }
+
     // ============= Metrics API =============
 
     pub async fn query_metrics(&self, query: &str, from: i64, to: i64) -> Result<MetricsResponse> {
@@ -136,6 +189,86 @@ impl DatadogClient {
         .await
     }
 
+    /// Run named metric queries and formulas over them through the v2 API
+    ///
+    /// `from`/`to` and `interval` are in milliseconds; `queries` are `(name, query)` pairs.
+    pub async fn query_timeseries(
+        &self,
+        from: i64,
+        to: i64,
+        interval: Option<i64>,
+        queries: &[(String, String)],
+        formulas: &[String],
+    ) -> Result<TimeseriesQueryResponse> {
+        let queries: Vec<_> = queries
+            .iter()
+            .map(|(name, query)| {
+                serde_json::json!({ "data_source": "metrics", "name": name, "query": query })
+            })
+            .collect();
+
+        let mut attributes = serde_json::json!({
+            "from": from,
+            "to": to,
+            "queries": queries,
+        });
+
+        if !formulas.is_empty() {
+            attributes["formulas"] = formulas
+                .iter()
+                .map(|f| serde_json::json!({ "formula": f }))
+                .collect();
+        }
+
+        if let Some(interval) = interval {
+            attributes["interval"] = serde_json::json!(interval);
+        }
+
+        let body = serde_json::json!({
+            "data": { "type": "timeseries_request", "attributes": attributes }
+        });
+
+        self.request(
+            reqwest::Method::POST,
+            "/api/v2/query/timeseries",
+            None,
+            Some(body),
+        )
+        .await
+    }
+
+    pub async fn query_scalar(
+        &self,
+        from: i64,
+        to: i64,
+        query: &str,
+        aggregator: &str,
+    ) -> Result<ScalarQueryResponse> {
+        let body = serde_json::json!({
+            "data": {
+                "type": "scalar_request",
+                "attributes": {
+                    "from": from,
+                    "to": to,
+                    "queries": [{
+                        "data_source": "metrics",
+                        "name": "query1",
+                        "query": query,
+                        "aggregator": aggregator,
+                    }],
+                },
+            }
+        });
+
+        self.request(
+            reqwest::Method::POST,
+            "/api/v2/query/scalar",
+            None,
+            Some(body),
+        )
+        .await
+    }
+
     // ============= Logs API =============
 
     pub async fn search_logs(
@@ -490,6 +623,8 @@ impl DatadogClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
     async fn test_client_new_with_default_site() {
@@ -547,6 +682,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_client_with_base_url() {
+        let client = DatadogClient::new(
+            "key".to_string(),
+            "app".to_string(),
+            Some("datadoghq.eu".to_string()),
+            30,
+            3,
+            None,
+        )
+        .unwrap()
+        .with_base_url("http://localhost:8080/");
+
+        assert_eq!(client.base_url, "http://localhost:8080");
+    }
+
     #[test]
     fn test_tag_filter() {
         let client = DatadogClient::new(
@@ -562,6 +713,101 @@ mod tests {
         assert_eq!(client.get_tag_filter(), Some("env:,service:"));
     }
 
+    async fn mock_client(server: &MockServer) -> DatadogClient {
+        DatadogClient::new("key".to_string(), "app".to_string(), None, 5, 3, None)
+            .unwrap()
+            .with_base_url(&server.uri())
+    }
+
+    #[tokio::test]
+    async fn test_request_does_not_retry_auth_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/monitor/1"))
+            .respond_with(
+                ResponseTemplate::new(403).set_body_string("{\"errors\":[\"Forbidden\"]}"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let error = mock_client(&server).await.get_monitor(1).await.unwrap_err();
+        assert!(matches!(error, DatadogError::AuthError(_)));
+        assert_eq!(error.status(), Some(403));
+        assert_eq!(error.response().unwrap().errors, vec!["Forbidden"]);
+    }
+
+    #[tokio::test]
+    async fn test_request_retries_server_error_with_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/hosts"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "0"))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/hosts"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "total_matching": 0,
+                "total_returned": 0,
+                "host_list": []
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let result = mock_client(&server)
+            .await
+            .list_hosts(None, None, None, None, None, None)
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_request_gives_up_when_rate_limit_exceeds_budget() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/monitor/1"))
+            .respond_with(ResponseTemplate::new(429).insert_header("X-RateLimit-Reset", "3600"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let error = mock_client(&server).await.get_monitor(1).await.unwrap_err();
+        let DatadogError::RateLimitError(details) = error else {
+            panic!("Expected RateLimitError");
+        };
+        assert_eq!(details.path, "/api/v1/monitor/1");
+        assert_eq!(details.rate_limit.unwrap().reset, Some(3600));
+    }
+
+    #[tokio::test]
+    async fn test_request_maps_404_to_not_found() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/dashboard/abc-def-ghi"))
+            .respond_with(
+                ResponseTemplate::new(404)
+                    .set_body_string("{\"errors\":[\"Dashboard not found\"]}"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let error = mock_client(&server)
+            .await
+            .get_dashboard("abc-def-ghi")
+            .await
+            .unwrap_err();
+        let DatadogError::NotFound(details) = error else {
+            panic!("Expected NotFound");
+        };
+        assert_eq!(details.status, 404);
+        assert_eq!(details.errors, vec!["Dashboard not found"]);
+    }
+
     #[test]
     fn test_custom_timeout_and_retries() {
         let client =
@@ -1,6 +1,22 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateResponse {
+    pub valid: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrgsResponse {
+    pub orgs: Option<Vec<Org>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Org {
+    pub name: Option<String>,
+    pub public_id: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MetricsResponse {
     pub status: String,
@@ -51,6 +67,74 @@ pub struct Unit {
     pub id: Option<i64>,
 }
 
+/// Response of `/api/v2/query/timeseries`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeseriesQueryResponse {
+    pub data: Option<TimeseriesQueryData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeseriesQueryData {
+    pub attributes: TimeseriesQueryAttributes,
+}
+
+/// `values[i]` holds the points of `series[i]`, all aligned on `times` (ms)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeseriesQueryAttributes {
+    #[serde(default)]
+    pub series: Vec<TimeseriesQuerySeries>,
+    #[serde(default)]
+    pub times: Vec<i64>,
+    #[serde(default)]
+    pub values: Vec<Vec<Option<f64>>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeseriesQuerySeries {
+    pub group_tags: Option<Vec<String>>,
+    pub query_index: Option<usize>,
+    pub unit: Option<Vec<Option<Unit>>>,
+}
+
+/// Response of `/api/v2/query/scalar`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScalarQueryResponse {
+    pub data: Option<ScalarQueryData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScalarQueryData {
+    pub attributes: ScalarQueryAttributes,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScalarQueryAttributes {
+    #[serde(default)]
+    pub columns: Vec<ScalarColumn>,
+}
+
+/// One column of a scalar response, row-aligned with every other column
+///
+/// `group` columns hold a tag list per row, `number` columns a value per row.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScalarColumn {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: String,
+    #[serde(default)]
+    pub values: Vec<serde_json::Value>,
+    pub meta: Option<ScalarColumnMeta>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScalarColumnMeta {
+    pub unit: Option<Vec<Option<Unit>>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LogsResponse {
     pub data: Option<Vec<LogEntry>>,
@@ -1,4 +1,20 @@
-use std::time::Duration;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::error::Error as StdError;
+use std::io::ErrorKind;
+use std::time::{Duration, SystemTime};
+
+use crate::error::RateLimitInfo;
+
+/// Upper bound on the total time a single request may spend waiting between retries
+pub const MAX_RETRY_ELAPSED: Duration = Duration::from_secs(60);
+
+/// Datadog rate-limit headers, see https://docs.datadoghq.com/api/latest/rate-limits/
+const RATE_LIMIT_LIMIT: &str = "x-ratelimit-limit";
+const RATE_LIMIT_PERIOD: &str = "x-ratelimit-period";
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+const RATE_LIMIT_NAME: &str = "x-ratelimit-name";
 
 /// Calculate exponential backoff duration for a given retry attempt
 ///
@@ -15,9 +31,111 @@ pub fn should_retry(current_retry: u32, max_retries: u32) -> bool {
     current_retry < max_retries
 }
 
+/// Check if an HTTP status is worth retrying
+///
+/// Rate limiting (429), request timeouts (408) and server errors (5xx) are
+/// transient. Every other client error (400, 401, 403, 404, ...) will fail the
+/// same way again and is returned immediately.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error()
+}
+
+/// Check if a transport error is worth retrying
+///
+/// Covers timeouts, failed connects and connections reset or dropped mid-request.
+pub fn is_retryable_error(error: &reqwest::Error) -> bool {
+    if error.is_timeout() || error.is_connect() {
+        return true;
+    }
+
+    let mut source = error.source();
+    while let Some(err) = source {
+        if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io_err.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+            );
+        }
+        source = err.source();
+    }
+
+    false
+}
+
+/// Read the server's guidance on how long to wait before retrying
+///
+/// `Retry-After` (delta seconds or HTTP date) takes precedence over Datadog's
+/// `X-RateLimit-Reset` (seconds until the rate-limit window resets).
+pub fn server_retry_hint(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+    };
+
+    if let Some(value) = header(RETRY_AFTER.as_str()) {
+        if let Ok(secs) = value.parse::<u64>() {
+            return Some(Duration::from_secs(secs));
+        }
+        if let Ok(date) = chrono::DateTime::parse_from_rfc2822(value) {
+            let secs = (date.timestamp() - chrono::Utc::now().timestamp()).max(0);
+            return Some(Duration::from_secs(secs as u64));
+        }
+    }
+
+    header(RATE_LIMIT_RESET)
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Collect Datadog's `X-RateLimit-*` headers, if the response carried any
+pub fn rate_limit_info(headers: &HeaderMap) -> Option<RateLimitInfo> {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    let number = |name| header(name).and_then(|v| v.trim().parse::<u64>().ok());
+
+    let info = RateLimitInfo {
+        limit: number(RATE_LIMIT_LIMIT),
+        period: number(RATE_LIMIT_PERIOD),
+        remaining: number(RATE_LIMIT_REMAINING),
+        reset: number(RATE_LIMIT_RESET),
+        name: header(RATE_LIMIT_NAME).map(str::to_string),
+    };
+
+    (info != RateLimitInfo::default()).then_some(info)
+}
+
+/// Delay before the given retry attempt
+///
+/// Uses the server hint when present, exponential backoff otherwise, plus up
+/// to 25% random jitter so concurrent clients don't retry in lockstep.
+pub fn retry_delay(retry_count: u32, hint: Option<Duration>) -> Duration {
+    let base = hint.unwrap_or_else(|| calculate_backoff(retry_count));
+    base + jitter(base / 4)
+}
+
+fn jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u128)
+        .unwrap_or_default();
+
+    Duration::from_nanos((nanos % max.as_nanos()) as u64)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use reqwest::header::HeaderValue;
 
     #[test]
     fn test_calculate_backoff_progression() {
@@ -59,4 +177,72 @@ mod tests {
         assert!(!should_retry(5, 5));
         assert!(!should_retry(0, 0));
     }
+
+    #[test]
+    fn test_is_retryable_status() {
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable_status(StatusCode::REQUEST_TIMEOUT));
+        assert!(is_retryable_status(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_retryable_status(StatusCode::FORBIDDEN));
+        assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!is_retryable_status(StatusCode::OK));
+    }
+
+    #[test]
+    fn test_server_retry_hint() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(server_retry_hint(&headers), None);
+
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("7"));
+        assert_eq!(server_retry_hint(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3"));
+        assert_eq!(server_retry_hint(&headers), Some(Duration::from_secs(3)));
+
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(server_retry_hint(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_rate_limit_info() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(rate_limit_info(&headers), None);
+
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("100"));
+        headers.insert("x-ratelimit-period", HeaderValue::from_static("60"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("42"));
+        headers.insert("x-ratelimit-name", HeaderValue::from_static("logs_search"));
+
+        let info = rate_limit_info(&headers).unwrap();
+        assert_eq!(info.limit, Some(100));
+        assert_eq!(info.period, Some(60));
+        assert_eq!(info.remaining, Some(0));
+        assert_eq!(info.reset, Some(42));
+        assert_eq!(info.name.as_deref(), Some("logs_search"));
+    }
+
+    #[test]
+    fn test_retry_delay_bounds() {
+        for attempt in 1..=3 {
+            let base = calculate_backoff(attempt);
+            let delay = retry_delay(attempt, None);
+            assert!(delay >= base);
+            assert!(delay <= base + base / 4);
+        }
+
+        let hint = Duration::from_secs(10);
+        let delay = retry_delay(1, Some(hint));
+        assert!(delay >= hint && delay <= hint + hint / 4);
+
+        assert_eq!(retry_delay(1, Some(Duration::ZERO)), Duration::ZERO);
+    }
 }
@@ -1,3 +1,5 @@
+use serde_json::Value;
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -5,8 +7,17 @@ pub enum DatadogError {
     #[error("API request failed: {0}")]
     ApiError(String),
 
+    #[error("API request failed: {0}")]
+    HttpError(Box<ApiErrorResponse>),
+
+    #[error("Not found: {0}")]
+    NotFound(Box<ApiErrorResponse>),
+
+    #[error("Authentication failed: {0}")]
+    AuthError(Box<ApiErrorResponse>),
+
     #[error("Authentication failed: {0}")]
-    AuthError(String),
+    CredentialError(String),
 
     #[error("Invalid date format: {0}")]
     DateParseError(String),
@@ -23,15 +34,213 @@ pub enum DatadogError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
-    #[error("Rate limit exceeded")]
-    RateLimitError,
+    #[error("Rate limit exceeded: {0}")]
+    RateLimitError(Box<ApiErrorResponse>),
 
     #[error("Timeout occurred")]
     TimeoutError,
+
+    #[error("Assertion failed: {0}")]
+    AssertionFailed(String),
 }
 
 pub type Result<T> = std::result::Result<T, DatadogError>;
 
+/// Error category used for process exit codes and machine-readable error output
+///
+/// | Exit code | Kind               | Cause                                     |
+/// |-----------|--------------------|-------------------------------------------|
+/// | 1         | `error`            | Any other failure                         |
+/// | 2         | `invalid_input`    | Bad arguments, time format or query (400) |
+/// | 3         | `auth`             | Missing or rejected keys (401, 403)       |
+/// | 4         | `not_found`        | Requested object does not exist (404)     |
+/// | 5         | `rate_limited`     | Datadog rate limit exceeded (429)         |
+/// | 6         | `network`          | Connection to the API failed              |
+/// | 7         | `timeout`          | Request timed out                         |
+/// | 8         | `server_error`     | Datadog returned a 5xx error              |
+/// | 9         | `assertion_failed` | A `--assert` condition did not hold       |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    Error,
+    InvalidInput,
+    Auth,
+    NotFound,
+    RateLimited,
+    Network,
+    Timeout,
+    ServerError,
+    AssertionFailed,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Error => 1,
+            ErrorKind::InvalidInput => 2,
+            ErrorKind::Auth => 3,
+            ErrorKind::NotFound => 4,
+            ErrorKind::RateLimited => 5,
+            ErrorKind::Network => 6,
+            ErrorKind::Timeout => 7,
+            ErrorKind::ServerError => 8,
+            ErrorKind::AssertionFailed => 9,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Error => "error",
+            ErrorKind::InvalidInput => "invalid_input",
+            ErrorKind::Auth => "auth",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::RateLimited => "rate_limited",
+            ErrorKind::Network => "network",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::ServerError => "server_error",
+            ErrorKind::AssertionFailed => "assertion_failed",
+        }
+    }
+
+    pub fn hint(self) -> Option<&'static str> {
+        match self {
+            ErrorKind::Error => None,
+            ErrorKind::InvalidInput => Some("Check the command arguments and query syntax"),
+            ErrorKind::Auth => Some(
+                "Check api_key/app_key with `datadog-cli config show` and that the site matches your organization",
+            ),
+            ErrorKind::NotFound => Some("Check the ID; the object may have been deleted"),
+            ErrorKind::RateLimited => {
+                Some("Wait for the rate limit window to reset or reduce request volume")
+            }
+            ErrorKind::Network => {
+                Some("Check network connectivity and the configured site or api_url")
+            }
+            ErrorKind::Timeout => {
+                Some("Narrow the time range or raise network.timeout_secs in the config")
+            }
+            ErrorKind::ServerError => {
+                Some("Datadog may be degraded; see https://status.datadoghq.com")
+            }
+            ErrorKind::AssertionFailed => None,
+        }
+    }
+}
+
+impl DatadogError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            DatadogError::AuthError(_) | DatadogError::CredentialError(_) => ErrorKind::Auth,
+            DatadogError::InvalidInput(_) | DatadogError::DateParseError(_) => {
+                ErrorKind::InvalidInput
+            }
+            DatadogError::NotFound(_) => ErrorKind::NotFound,
+            DatadogError::RateLimitError(_) => ErrorKind::RateLimited,
+            DatadogError::TimeoutError => ErrorKind::Timeout,
+            DatadogError::AssertionFailed(_) => ErrorKind::AssertionFailed,
+            DatadogError::NetworkError(e) if e.is_timeout() => ErrorKind::Timeout,
+            DatadogError::NetworkError(e) if e.is_decode() => ErrorKind::Error,
+            DatadogError::NetworkError(_) => ErrorKind::Network,
+            DatadogError::HttpError(r) => match r.status {
+                400 | 422 => ErrorKind::InvalidInput,
+                500..=599 => ErrorKind::ServerError,
+                _ => ErrorKind::Error,
+            },
+            DatadogError::ApiError(_) | DatadogError::JsonError(_) | DatadogError::IoError(_) => {
+                ErrorKind::Error
+            }
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.kind().exit_code()
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        self.response().map(|r| r.status)
+    }
+
+    pub fn response(&self) -> Option<&ApiErrorResponse> {
+        match self {
+            DatadogError::HttpError(r)
+            | DatadogError::AuthError(r)
+            | DatadogError::NotFound(r)
+            | DatadogError::RateLimitError(r) => Some(r),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiErrorResponse {
+    pub status: u16,
+    pub path: String,
+    pub errors: Vec<String>,
+    pub rate_limit: Option<RateLimitInfo>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimitInfo {
+    pub limit: Option<u64>,
+    pub period: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset: Option<u64>,
+    pub name: Option<String>,
+}
+
+impl ApiErrorResponse {
+    pub fn new(status: u16, path: &str, body: &str, rate_limit: Option<RateLimitInfo>) -> Self {
+        Self {
+            status,
+            path: path.to_string(),
+            errors: parse_error_messages(body),
+            rate_limit,
+        }
+    }
+}
+
+impl fmt::Display for ApiErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HTTP {} {}", self.status, self.path)?;
+        if !self.errors.is_empty() {
+            write!(f, ": {}", self.errors.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+// Datadog returns `{"errors": ["..."]}` on v1 endpoints and JSON:API style
+// `{"errors": [{"title": "...", "detail": "..."}]}` on v2; anything else is kept verbatim.
+fn parse_error_messages(body: &str) -> Vec<String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Vec::new();
+    }
+
+    let errors = serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v.get("errors").and_then(|e| e.as_array()).cloned());
+
+    match errors {
+        Some(errors) => errors
+            .iter()
+            .filter_map(|e| match e {
+                Value::String(s) => Some(s.clone()),
+                Value::Object(obj) => {
+                    let title = obj.get("title").and_then(|t| t.as_str());
+                    let detail = obj.get("detail").and_then(|d| d.as_str());
+                    match (title, detail) {
+                        (Some(t), Some(d)) => Some(format!("{}: {}", t, d)),
+                        (Some(m), None) | (None, Some(m)) => Some(m.to_string()),
+                        (None, None) => Some(e.to_string()),
+                    }
+                }
+                _ => None,
+            })
+            .collect(),
+        None => vec![body.to_string()],
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -46,7 +255,7 @@ mod tests {
 
     #[test]
     fn test_auth_error_display() {
-        let error = DatadogError::AuthError("Invalid credentials".to_string());
+        let error = DatadogError::CredentialError("Invalid credentials".to_string());
         let error_msg = format!("{}", error);
         assert!(error_msg.contains("Authentication failed"));
         assert!(error_msg.contains("Invalid credentials"));
@@ -68,9 +277,106 @@ mod tests {
 
     #[test]
     fn test_rate_limit_error_display() {
-        let error = DatadogError::RateLimitError;
+        let error = DatadogError::RateLimitError(Box::new(ApiErrorResponse::new(
+            429,
+            "/api/v1/query",
+            "",
+            None,
+        )));
         let error_msg = format!("{}", error);
         assert!(error_msg.contains("Rate limit exceeded"));
+        assert_eq!(error.status(), Some(429));
+    }
+
+    #[test]
+    fn test_error_kind_exit_codes() {
+        let http = |status| {
+            DatadogError::HttpError(Box::new(ApiErrorResponse::new(status, "/api", "", None)))
+        };
+
+        assert_eq!(DatadogError::ApiError("x".into()).exit_code(), 1);
+        assert_eq!(DatadogError::InvalidInput("x".into()).exit_code(), 2);
+        assert_eq!(DatadogError::DateParseError("x".into()).exit_code(), 2);
+        assert_eq!(http(400).exit_code(), 2);
+        assert_eq!(DatadogError::CredentialError("x".into()).exit_code(), 3);
+        assert_eq!(
+            DatadogError::AuthError(Box::new(ApiErrorResponse::new(403, "/api", "", None)))
+                .exit_code(),
+            3
+        );
+        assert_eq!(
+            DatadogError::NotFound(Box::new(ApiErrorResponse::new(404, "/api", "", None)))
+                .exit_code(),
+            4
+        );
+        assert_eq!(
+            DatadogError::RateLimitError(Box::new(ApiErrorResponse::new(429, "/api", "", None)))
+                .exit_code(),
+            5
+        );
+        assert_eq!(DatadogError::TimeoutError.exit_code(), 7);
+        assert_eq!(http(503).exit_code(), 8);
+        assert_eq!(http(409).exit_code(), 1);
+        assert_eq!(DatadogError::AssertionFailed("x".into()).exit_code(), 9);
+    }
+
+    #[test]
+    fn test_error_kind_names_and_hints() {
+        assert_eq!(ErrorKind::NotFound.as_str(), "not_found");
+        assert_eq!(ErrorKind::RateLimited.as_str(), "rate_limited");
+        assert!(ErrorKind::Error.hint().is_none());
+        assert!(ErrorKind::Auth.hint().unwrap().contains("config show"));
+    }
+
+    #[test]
+    fn test_parse_v1_error_body() {
+        let response = ApiErrorResponse::new(
+            400,
+            "/api/v1/query",
+            r#"{"errors": ["Error parsing query", "unexpected token"]}"#,
+            None,
+        );
+        assert_eq!(
+            response.errors,
+            vec!["Error parsing query", "unexpected token"]
+        );
+        assert_eq!(
+            response.to_string(),
+            "HTTP 400 /api/v1/query: Error parsing query; unexpected token"
+        );
+    }
+
+    #[test]
+    fn test_parse_v2_error_body() {
+        let body = include_str!("../tests/fixtures/error_responses.json");
+        let response = ApiErrorResponse::new(400, "/api/v2/logs/events/search", body, None);
+        assert_eq!(
+            response.errors,
+            vec!["Bad Request: Missing required parameter: query"]
+        );
+    }
+
+    #[test]
+    fn test_parse_non_json_error_body() {
+        let response = ApiErrorResponse::new(502, "/api/v1/hosts", "Bad Gateway", None);
+        assert_eq!(response.errors, vec!["Bad Gateway"]);
+
+        let response = ApiErrorResponse::new(502, "/api/v1/hosts", "  ", None);
+        assert!(response.errors.is_empty());
+        assert_eq!(response.to_string(), "HTTP 502 /api/v1/hosts");
+    }
+
+    #[test]
+    fn test_not_found_carries_response() {
+        let error = DatadogError::NotFound(Box::new(ApiErrorResponse::new(
+            404,
+            "/api/v1/monitor/1",
+            r#"{"errors": ["Monitor not found"]}"#,
+            None,
+        )));
+        assert_eq!(error.status(), Some(404));
+        assert!(format!("{}", error).contains("Monitor not found"));
+        assert_eq!(DatadogError::TimeoutError.status(), None);
     }
 
     #[test]
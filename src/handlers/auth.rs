@@ -0,0 +1,363 @@
+use serde_json::{Value, json};
+use std::sync::Arc;
+
+use crate::datadog::DatadogClient;
+use crate::error::{DatadogError, Result};
+use crate::handlers::common::{ParameterParser, ResponseFormatter, TimeHandler};
+
+/// Window used by the scope probes that require a time range
+const PROBE_WINDOW_SECS: i64 = 15 * 60;
+
+/// Scope behind `/api/v1/org`, which the application key check reads
+const ORG_SCOPE: &str = "org_management";
+
+pub struct AuthHandler;
+
+impl TimeHandler for AuthHandler {}
+impl ResponseFormatter for AuthHandler {}
+impl ParameterParser for AuthHandler {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ProbeStatus {
+    Granted,
+    Missing,
+    Unauthorized,
+    Error,
+}
+
+impl ProbeStatus {
+    fn classify<T>(result: &Result<T>) -> Self {
+        match result {
+            Ok(_) => Self::Granted,
+            Err(e) => match e.status() {
+                Some(401) => Self::Unauthorized,
+                Some(403) => Self::Missing,
+                _ => Self::Error,
+            },
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Granted => "ok",
+            Self::Missing => "missing",
+            Self::Unauthorized => "unauthorized",
+            Self::Error => "error",
+        }
+    }
+}
+
+impl AuthHandler {
+    /// Verify the API key and the application key with one request each
+    ///
+    /// With `scopes`, the read scope each command needs is also probed with that
+    /// command's cheapest read request; a 403 means the application key is scoped
+    /// without it.
+    pub async fn check(client: Arc<DatadogClient>, params: &Value) -> Result<Value> {
+        let handler = AuthHandler;
+
+        // Only a rejected key is a check result; network and server failures are errors
+        let api_key = match client.validate_api_key().await {
+            Err(e) if !matches!(e.status(), Some(401 | 403)) => return Err(e),
+            result => result,
+        };
+        let api_key_valid = matches!(&api_key, Ok(r) if r.valid);
+
+        let mut report = json!({
+            "valid": false,
+            "site": handler.extract_string(params, "site"),
+            "api_url": handler.extract_string(params, "api_url"),
+            "api_key": {
+                "valid": api_key_valid,
+                "error": api_key.err().map(|e| e.to_string()),
+            },
+            "app_key": { "valid": false, "error": null },
+            "org": null,
+        });
+
+        if !api_key_valid {
+            report["app_key"]["error"] = json!("Skipped: API key is invalid");
+            return Ok(handler.format_detail(report));
+        }
+
+        // As for the API key, only 401/403 are results; a 403 means the key works but lacks org access
+        let org = match client.get_org().await {
+            Err(e) if !matches!(e.status(), Some(401 | 403)) => return Err(e),
+            result => result,
+        };
+        let scopes = if params["scopes"].as_bool().unwrap_or(false) {
+            Some(Self::probe_scopes(&client).await?)
+        } else {
+            None
+        };
+
+        let app_key_valid = ProbeStatus::classify(&org) != ProbeStatus::Unauthorized;
+        let mut missing: Vec<Value> = Vec::new();
+
+        match org {
+            Ok(response) => {
+                report["org"] = response
+                    .orgs
+                    .and_then(|orgs| orgs.into_iter().next())
+                    .map(|o| json!({ "name": o.name, "public_id": o.public_id }))
+                    .unwrap_or(Value::Null);
+            }
+            Err(e) if !app_key_valid => report["app_key"]["error"] = json!(e.to_string()),
+            Err(_) => missing.push(json!(ORG_SCOPE)),
+        }
+
+        report["valid"] = json!(app_key_valid);
+        report["app_key"]["valid"] = json!(app_key_valid);
+
+        if let Some(scopes) = scopes {
+            // Org access is informational; only scopes a command needs fail the check
+            let command_missing: Vec<Value> = if app_key_valid {
+                scopes
+                    .iter()
+                    .filter(|s| s["status"] == "missing")
+                    .map(|s| s["scope"].clone())
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            report["valid"] = json!(app_key_valid && command_missing.is_empty());
+            missing.extend(command_missing);
+            report["missing_scopes"] = json!(missing);
+            report["scopes"] = json!(scopes);
+        } else if !missing.is_empty() {
+            report["missing_scopes"] = json!(missing);
+        }
+
+        Ok(handler.format_detail(report))
+    }
+
+    async fn probe_scopes(client: &DatadogClient) -> Result<Vec<Value>> {
+        let handler = AuthHandler;
+
+        let to = chrono::Utc::now().timestamp();
+        let from = to - PROBE_WINDOW_SECS;
+        let from_iso = handler.timestamp_to_iso8601(from)?;
+        let to_iso = handler.timestamp_to_iso8601(to)?;
+
+        let (monitors, dashboards, hosts, events, metrics, logs, spans, services, rum) = tokio::join!(
+            client.list_monitors(None, None, Some(0), Some(1)),
+            client.list_dashboards(Some(1), Some(0), None, None),
+            client.list_hosts(None, Some(from), None, None, Some(0), Some(1)),
+            client.query_events(from, to, None, None, None),
+            client.query_metrics("avg:datadog.estimated_usage.hosts{*}", from, to),
+            client.search_logs("*", &from_iso, &to_iso, 1, None, None),
+            client.list_spans("*", &from_iso, &to_iso, 1, None, None),
+            client.get_service_catalog(Some(1), Some(0), None),
+            client.search_rum_events("*", &from_iso, &to_iso, 1, None, None),
+        );
+
+        Ok(vec![
+            Self::scope_entry("monitors", "monitors_read", monitors),
+            Self::scope_entry("dashboards", "dashboards_read", dashboards),
+            Self::scope_entry("hosts", "hosts_read", hosts),
+            Self::scope_entry("events", "events_read", events),
+            Self::scope_entry("metrics", "timeseries_query", metrics),
+            Self::scope_entry("logs", "logs_read_data", logs),
+            Self::scope_entry("spans", "apm_read", spans),
+            Self::scope_entry("services", "apm_service_catalog_read", services),
+            Self::scope_entry("rum", "rum_apps_read", rum),
+        ])
+    }
+
+    fn scope_entry<T>(command: &str, scope: &str, result: Result<T>) -> Value {
+        json!({
+            "scope": scope,
+            "command": command,
+            "status": ProbeStatus::classify(&result).as_str(),
+            "error": result.err().map(|e| e.to_string()),
+        })
+    }
+
+    /// Turn a failed check report into an error so the process exits non-zero
+    pub fn ensure_valid(report: &Value) -> Result<()> {
+        let data = &report["data"];
+        if data["valid"].as_bool().unwrap_or(false) {
+            return Ok(());
+        }
+
+        let message = if data["api_key"]["valid"] != true {
+            "API key is invalid".to_string()
+        } else if data["app_key"]["valid"] != true {
+            "Application key is invalid".to_string()
+        } else {
+            let missing: Vec<&str> = data["missing_scopes"]
+                .as_array()
+                .map(|a| a.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            format!("Application key is missing scopes: {}", missing.join(", "))
+        };
+
+        Err(DatadogError::CredentialError(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn mock_client(server: &MockServer) -> Arc<DatadogClient> {
+        Arc::new(
+            DatadogClient::new("key".into(), "app".into(), None, 5, 0, None)
+                .unwrap()
+                .with_base_url(&server.uri()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_check_invalid_api_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/validate"))
+            .respond_with(ResponseTemplate::new(403).set_body_string(r#"{"errors":["Forbidden"]}"#))
+            .mount(&server)
+            .await;
+
+        let report = AuthHandler::check(mock_client(&server), &json!({}))
+            .await
+            .unwrap();
+
+        assert_eq!(report["data"]["valid"], false);
+        assert_eq!(report["data"]["api_key"]["valid"], false);
+        assert!(
+            AuthHandler::ensure_valid(&report)
+                .unwrap_err()
+                .to_string()
+                .contains("API key is invalid")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_reports_missing_scopes() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/validate"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"valid":true}"#))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/org"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(r#"{"orgs":[{"name":"Acme","public_id":"abc"}]}"#),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/monitor"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/logs/events/search"))
+            .respond_with(ResponseTemplate::new(403).set_body_string(
+                r#"{"errors":[{"title":"Forbidden","detail":"Missing logs_read_data"}]}"#,
+            ))
+            .mount(&server)
+            .await;
+
+        let report = AuthHandler::check(
+            mock_client(&server),
+            &json!({"site": "datadoghq.com", "scopes": true}),
+        )
+        .await
+        .unwrap();
+        let data = &report["data"];
+
+        assert_eq!(data["site"], "datadoghq.com");
+        assert_eq!(data["org"]["name"], "Acme");
+        assert_eq!(data["app_key"]["valid"], true);
+        assert_eq!(data["valid"], false);
+        assert_eq!(data["missing_scopes"], json!(["logs_read_data"]));
+
+        let monitors = data["scopes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["command"] == "monitors")
+            .unwrap();
+        assert_eq!(monitors["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_check_skips_scope_probes_by_default() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/validate"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"valid":true}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/org"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"orgs":[]}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let report = AuthHandler::check(mock_client(&server), &json!({}))
+            .await
+            .unwrap();
+        let data = &report["data"];
+
+        assert_eq!(data["valid"], true);
+        assert_eq!(data["app_key"]["valid"], true);
+        assert!(data.get("scopes").is_none());
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+        assert!(AuthHandler::ensure_valid(&report).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_org_forbidden_is_missing_scope() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/validate"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"valid":true}"#))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/org"))
+            .respond_with(ResponseTemplate::new(403).set_body_string(r#"{"errors":["Forbidden"]}"#))
+            .mount(&server)
+            .await;
+
+        let report = AuthHandler::check(mock_client(&server), &json!({}))
+            .await
+            .unwrap();
+        let data = &report["data"];
+
+        assert_eq!(data["app_key"]["valid"], true);
+        assert_eq!(data["app_key"]["error"], Value::Null);
+        assert_eq!(data["missing_scopes"], json!([ORG_SCOPE]));
+        assert_eq!(data["valid"], true);
+        assert!(AuthHandler::ensure_valid(&report).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_check_org_server_error_is_not_invalid_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/validate"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"valid":true}"#))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/org"))
+            .respond_with(ResponseTemplate::new(500).set_body_string(r#"{"errors":["Internal"]}"#))
+            .mount(&server)
+            .await;
+
+        let err = AuthHandler::check(mock_client(&server), &json!({}))
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.status(), Some(500));
+        assert_eq!(err.kind(), crate::error::ErrorKind::ServerError);
+    }
+}
@@ -15,6 +15,11 @@ pub struct PaginationInfo {
     pub has_next: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub next_offset: Option<usize>,
+    /// Set instead of `next_offset` for APIs that take a page number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_page: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 impl PaginationInfo {
@@ -25,6 +30,8 @@ impl PaginationInfo {
             page_size: limit,
             has_next: result_count >= limit,
             next_offset: None,
+            next_page: None,
+            next_cursor: None,
         }
     }
 
@@ -39,16 +46,37 @@ impl PaginationInfo {
             page_size: count,
             has_next,
             next_offset: if has_next { Some(next_offset) } else { None },
+            next_page: None,
+            next_cursor: None,
+        }
+    }
+
+    /// Offset page from an API that reports no total; a full page means more may follow
+    pub fn from_offset_open(start: usize, count: usize, returned: usize) -> Self {
+        let mut info = Self::from_offset(start + returned, start, count);
+        if returned >= count {
+            info.has_next = true;
+            info.next_offset = Some(start + count);
         }
+        info
     }
 
-    pub fn from_cursor(total: usize, page_size: usize, has_cursor: bool) -> Self {
+    /// Page-number variant of `from_offset_open`, resumed with `next_page`
+    pub fn from_page_open(page: usize, page_size: usize, returned: usize) -> Self {
+        let mut info = Self::from_offset_open(page * page_size, page_size, returned);
+        info.next_page = info.next_offset.take().map(|_| page + 1);
+        info
+    }
+
+    pub fn from_cursor(total: usize, page_size: usize, next_cursor: Option<String>) -> Self {
         Self {
             total,
             page: 0,
             page_size,
-            has_next: has_cursor,
+            has_next: next_cursor.is_some(),
             next_offset: None,
+            next_page: None,
+            next_cursor,
         }
     }
 }
@@ -303,6 +331,25 @@ mod tests {
         assert_eq!(page_empty.len(), 0);
     }
 
+    #[test]
+    fn test_pagination_from_offset_open() {
+        let full = PaginationInfo::from_offset_open(200, 100, 100);
+        assert_eq!(full.page, 2);
+        assert!(full.has_next);
+        assert_eq!(full.next_offset, Some(300));
+
+        let short = PaginationInfo::from_offset_open(200, 100, 40);
+        assert_eq!(short.total, 240);
+        assert!(!short.has_next);
+        assert_eq!(short.next_offset, None);
+
+        let paged = PaginationInfo::from_page_open(2, 100, 100);
+        assert_eq!(paged.page, 2);
+        assert_eq!(paged.next_offset, None);
+        assert_eq!(paged.next_page, Some(3));
+        assert_eq!(PaginationInfo::from_page_open(2, 100, 40).next_page, None);
+    }
+
     #[test]
     fn test_response_formatter_list() {
         let handler = TestHandler;
@@ -39,7 +39,8 @@ impl DashboardsHandler {
             })
             .collect();
 
-        let pagination = PaginationInfo::from_offset(data.len(), start as usize, count as usize);
+        let pagination =
+            PaginationInfo::from_offset_open(start as usize, count as usize, data.len());
 
         Ok(handler.format_list(json!(data), Some(serde_json::to_value(pagination)?), None))
     }
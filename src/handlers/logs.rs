@@ -1,14 +1,38 @@
 use serde_json::{Value, json};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::datadog::DatadogClient;
 use crate::datadog::models::{LogsCompute, LogsGroupBy, LogsGroupBySort};
-use crate::error::Result;
+use crate::error::{DatadogError, ErrorKind, Result};
 use crate::handlers::common::{
     PaginationInfo, ParameterParser, ResponseFilter, ResponseFormatter, TagFilter, TimeHandler,
     TimeParams,
 };
 
+/// How far back each `tail` poll looks, so late-indexed logs are still picked up
+pub const TAIL_OVERLAP_SECS: i64 = 60;
+
+/// Longest pause between polls while `tail` backs off from transient errors
+pub const TAIL_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Aggregations accepted by the log analytics API
+const LOG_AGGREGATIONS: &[&str] = &[
+    "count",
+    "cardinality",
+    "pc75",
+    "pc90",
+    "pc95",
+    "pc98",
+    "pc99",
+    "sum",
+    "min",
+    "max",
+    "avg",
+    "median",
+];
+
 pub struct LogsHandler;
 
 impl TimeHandler for LogsHandler {}
@@ -18,6 +42,96 @@ impl ResponseFormatter for LogsHandler {}
 impl ParameterParser for LogsHandler {}
 
 impl LogsHandler {
+    /// Parse a `--group-by` spec: `facet[:limit]`, e.g. `service:10` or `@http.status_code`
+    pub fn parse_group_by(spec: &str) -> Result<Value> {
+        let (facet, limit) = match spec.rsplit_once(':') {
+            Some((facet, limit)) if limit.chars().all(|c| c.is_ascii_digit()) => {
+                let limit: i64 = limit.parse().map_err(|_| {
+                    DatadogError::InvalidInput(format!("Invalid group-by limit in '{}'", spec))
+                })?;
+                (facet, Some(limit))
+            }
+            _ => (spec, None),
+        };
+
+        if facet.trim().is_empty() || limit == Some(0) {
+            return Err(DatadogError::InvalidInput(format!(
+                "Invalid group-by '{}': expected FACET[:LIMIT], e.g. service:10",
+                spec
+            )));
+        }
+
+        Ok(json!({ "facet": facet.trim(), "limit": limit }))
+    }
+
+    /// Parse a `--compute` spec: `aggregation[:metric]`, e.g. `count` or `pc95:@duration`
+    pub fn parse_compute(spec: &str) -> Result<Value> {
+        let (aggregation, metric) = match spec.split_once(':') {
+            Some((aggregation, metric)) => (aggregation.trim(), Some(metric.trim())),
+            None => (spec.trim(), None),
+        };
+
+        if !LOG_AGGREGATIONS.contains(&aggregation) {
+            return Err(DatadogError::InvalidInput(format!(
+                "Invalid compute '{}': unknown aggregation '{}' (expected one of: {})",
+                spec,
+                aggregation,
+                LOG_AGGREGATIONS.join(", ")
+            )));
+        }
+
+        match (aggregation, metric) {
+            ("count", Some(_)) => Err(DatadogError::InvalidInput(format!(
+                "Invalid compute '{}': count takes no metric",
+                spec
+            ))),
+            ("count", None) => Ok(json!({ "aggregation": "count" })),
+            (_, Some(metric)) if !metric.is_empty() => {
+                Ok(json!({ "aggregation": aggregation, "metric": metric }))
+            }
+            _ => Err(DatadogError::InvalidInput(format!(
+                "Invalid compute '{}': {} needs a metric, e.g. {}:@duration",
+                spec, aggregation, aggregation
+            ))),
+        }
+    }
+
+    /// Accept `UTC`/`GMT` with an optional offset (`UTC+9`, `+09:00`) or a tz database name
+    pub fn validate_timezone(timezone: &str) -> Result<()> {
+        let (named_utc, offset) = match timezone
+            .strip_prefix("UTC")
+            .or_else(|| timezone.strip_prefix("GMT"))
+        {
+            Some(offset) => (true, offset),
+            None => (false, timezone),
+        };
+        let is_offset = (named_utc && offset.is_empty())
+            || offset
+                .strip_prefix(['+', '-'])
+                .and_then(|o| {
+                    let (hours, minutes) = o.split_once(':').unwrap_or((o, "00"));
+                    Some((hours.parse::<u8>().ok()?, minutes.parse::<u8>().ok()?))
+                })
+                .is_some_and(|(h, m)| h <= 14 && m < 60);
+
+        let is_named = timezone.contains('/')
+            && timezone.split('/').all(|part| {
+                !part.is_empty()
+                    && part
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'))
+            });
+
+        if is_offset || is_named {
+            Ok(())
+        } else {
+            Err(DatadogError::InvalidInput(format!(
+                "Invalid timezone '{}': use UTC, an offset like UTC+9, or a name like Asia/Seoul",
+                timezone
+            )))
+        }
+    }
+
     pub async fn search(client: Arc<DatadogClient>, params: &Value) -> Result<Value> {
         let handler = LogsHandler;
 
@@ -40,6 +154,8 @@ impl LogsHandler {
         }
 
         let tag_filter = handler.extract_tag_filter(params, &client);
+        // Custom attributes are only kept when --fields or --query may select them
+        let keep_attributes = params["attributes"].as_bool().unwrap_or(false);
 
         let logs: Vec<Value> = response
             .data
@@ -73,19 +189,20 @@ impl LogsHandler {
                 {
                     entry["tags"] = json!(tags_vec);
                 }
+                if keep_attributes
+                    && let Some(custom) = attrs.and_then(|a| a.attributes.as_ref())
+                    && !custom.is_empty()
+                {
+                    entry["attributes"] = json!(custom);
+                }
 
                 entry
             })
             .collect();
 
-        let has_cursor = response
-            .meta
-            .as_ref()
-            .and_then(|m| m.page.as_ref())
-            .and_then(|p| p.after.as_ref())
-            .is_some();
+        let next_cursor = response.meta.and_then(|m| m.page).and_then(|p| p.after);
 
-        let pagination = PaginationInfo::from_cursor(logs.len(), limit as usize, has_cursor);
+        let pagination = PaginationInfo::from_cursor(logs.len(), limit as usize, next_cursor);
 
         Ok(json!({
             "data": logs,
@@ -93,6 +210,62 @@ impl LogsHandler {
         }))
     }
 
+    /// Poll `search` forever and hand each batch of unseen logs to `on_logs`
+    ///
+    /// Every poll fetches the newest `limit` logs of a window that overlaps the
+    /// previous ones by `TAIL_OVERLAP_SECS`; logs already printed are skipped by
+    /// id and new ones are emitted oldest first. Runs until the future is dropped.
+    ///
+    /// Rate limits, server errors, timeouts and network failures are reported to
+    /// `on_retry` with the delay before the next poll, which backs off up to
+    /// `TAIL_MAX_BACKOFF`; any other error ends the tail.
+    pub async fn tail(
+        client: Arc<DatadogClient>,
+        params: &Value,
+        mut on_logs: impl FnMut(Vec<Value>) -> Result<()>,
+        mut on_retry: impl FnMut(&DatadogError, Duration),
+    ) -> Result<()> {
+        let handler = LogsHandler;
+
+        let interval = Duration::from_secs(params["interval"].as_u64().unwrap_or(5).max(1));
+        let TimeParams::Timestamp { mut from, .. } = handler.parse_time(params, 1)?;
+        let mut seen = HashMap::new();
+        let mut failures = 0;
+
+        loop {
+            let now = chrono::Utc::now().timestamp();
+            let mut poll = params.clone();
+            poll["from"] = json!(from.to_string());
+            poll["to"] = json!(now.to_string());
+            poll["sort"] = json!("-timestamp");
+            poll["cursor"] = Value::Null;
+
+            let page = match Self::search(client.clone(), &poll).await {
+                Ok(page) => page,
+                Err(e) if is_transient(&e) => {
+                    failures += 1;
+                    let delay = tail_backoff(interval, failures, &e);
+                    on_retry(&e, delay);
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            failures = 0;
+            let logs = page["data"].as_array().cloned().unwrap_or_default();
+            let new_logs = take_unseen(logs, &mut seen);
+
+            if !new_logs.is_empty() {
+                on_logs(new_logs)?;
+            }
+
+            from = from.max(now - TAIL_OVERLAP_SECS);
+            seen.retain(|_, ts| *ts >= from * 1000);
+
+            tokio::time::sleep(interval).await;
+        }
+    }
+
     pub async fn aggregate(client: Arc<DatadogClient>, params: &Value) -> Result<Value> {
         let handler = LogsHandler;
 
@@ -137,15 +310,29 @@ impl LogsHandler {
             }])
         };
 
+        // Without an explicit sort, groups are ranked by the first compute
+        let default_sort = compute
+            .as_ref()
+            .and_then(|c| c.first())
+            .map(|c| LogsGroupBySort {
+                order: Some("desc".to_string()),
+                sort_type: Some("measure".to_string()),
+                aggregation: Some(c.aggregation.clone()),
+                metric: c.metric.clone(),
+            });
+
         let group_by = params["group_by"].as_array().map(|arr| {
             arr.iter()
                 .map(|g| {
-                    let sort = g["sort"].as_object().map(|s| LogsGroupBySort {
-                        order: s["order"].as_str().map(|v| v.to_string()),
-                        sort_type: Some(s["type"].as_str().unwrap_or("measure").to_string()),
-                        aggregation: s["aggregation"].as_str().map(|v| v.to_string()),
-                        metric: s["metric"].as_str().map(|v| v.to_string()),
-                    });
+                    let sort = g["sort"]
+                        .as_object()
+                        .map(|s| LogsGroupBySort {
+                            order: s["order"].as_str().map(|v| v.to_string()),
+                            sort_type: Some(s["type"].as_str().unwrap_or("measure").to_string()),
+                            aggregation: s["aggregation"].as_str().map(|v| v.to_string()),
+                            metric: s["metric"].as_str().map(|v| v.to_string()),
+                        })
+                        .or_else(|| default_sort.clone());
 
                     LogsGroupBy {
                         facet: g["facet"].as_str().unwrap_or("status").to_string(),
@@ -158,6 +345,7 @@ impl LogsHandler {
         });
 
         let timezone = params["timezone"].as_str().map(|s| s.to_string());
+        let labels = compute_labels(compute.as_deref().unwrap_or_default());
 
         let response = client
             .aggregate_logs(&query, &from, &to, compute, group_by, timezone.clone())
@@ -175,9 +363,16 @@ impl LogsHandler {
             "from": from,
             "to": to,
             "timezone": timezone,
+            "computes": labels,
             "buckets_count": buckets_count
         });
 
+        let data = if params["raw"].as_bool().unwrap_or(false) {
+            data
+        } else {
+            json!(flatten_buckets(&data))
+        };
+
         Ok(handler.format_list(data, None, Some(meta)))
     }
 
@@ -199,12 +394,23 @@ impl LogsHandler {
         let aggregation = params["aggregation"].as_str().unwrap_or("count");
         let timezone = params["timezone"].as_str().map(|s| s.to_string());
 
-        let compute = vec![LogsCompute {
-            aggregation: aggregation.to_string(),
-            compute_type: Some("timeseries".to_string()),
-            interval: Some(interval.to_string()),
-            metric,
-        }];
+        let compute: Vec<LogsCompute> = match params["compute"].as_array() {
+            Some(computes) if !computes.is_empty() => computes
+                .iter()
+                .map(|c| LogsCompute {
+                    aggregation: c["aggregation"].as_str().unwrap_or("count").to_string(),
+                    compute_type: Some("timeseries".to_string()),
+                    interval: Some(interval.to_string()),
+                    metric: c["metric"].as_str().map(|s| s.to_string()),
+                })
+                .collect(),
+            _ => vec![LogsCompute {
+                aggregation: aggregation.to_string(),
+                compute_type: Some("timeseries".to_string()),
+                interval: Some(interval.to_string()),
+                metric,
+            }],
+        };
 
         let group_by = params["group_by"].as_array().map(|arr| {
             arr.iter()
@@ -217,6 +423,7 @@ impl LogsHandler {
                 .collect()
         });
 
+        let labels = compute_labels(&compute);
         let response = client
             .aggregate_logs(
                 &query,
@@ -242,17 +449,234 @@ impl LogsHandler {
             "interval": interval,
             "aggregation": aggregation,
             "timezone": timezone,
+            "computes": labels,
             "buckets_count": buckets_count
         });
 
+        let data = if params["raw"].as_bool().unwrap_or(false) {
+            data
+        } else {
+            json!(flatten_buckets(&data))
+        };
+
         Ok(handler.format_list(data, None, Some(meta)))
     }
 }
 
+/// Name each compute column (`c0`, `c1`, ...) after the aggregation it holds
+fn compute_labels(compute: &[LogsCompute]) -> Value {
+    let labels: serde_json::Map<String, Value> = compute
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let label = match &c.metric {
+                Some(metric) => format!("{}:{}", c.aggregation, metric),
+                None => c.aggregation.clone(),
+            };
+            (format!("c{}", i), json!(label))
+        })
+        .collect();
+    Value::Object(labels)
+}
+
+/// Turn analytics buckets into flat records
+///
+/// Each bucket's `by` facets and scalar computes become columns of one row;
+/// timeseries computes are expanded to one row per point, keyed by `timestamp`.
+fn flatten_buckets(data: &Value) -> Vec<Value> {
+    let Some(buckets) = data["buckets"].as_array() else {
+        return Vec::new();
+    };
+
+    let mut rows = Vec::new();
+    for bucket in buckets {
+        let mut base = bucket["by"].as_object().cloned().unwrap_or_default();
+        let mut points: BTreeMap<String, serde_json::Map<String, Value>> = BTreeMap::new();
+
+        for (id, value) in bucket["computes"].as_object().into_iter().flatten() {
+            match value.as_array() {
+                Some(series) => {
+                    for point in series {
+                        let time = match &point["time"] {
+                            Value::String(t) => t.clone(),
+                            other => other.to_string(),
+                        };
+                        points
+                            .entry(time)
+                            .or_default()
+                            .insert(id.clone(), point["value"].clone());
+                    }
+                }
+                None => {
+                    base.insert(id.clone(), value.clone());
+                }
+            }
+        }
+
+        if points.is_empty() {
+            rows.push(Value::Object(base));
+            continue;
+        }
+        for (time, values) in points {
+            let mut row = base.clone();
+            row.insert("timestamp".to_string(), json!(time));
+            row.extend(values);
+            rows.push(Value::Object(row));
+        }
+    }
+    rows
+}
+
+/// Errors worth polling through: rate limits, server, network and timeout failures
+fn is_transient(error: &DatadogError) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::RateLimited | ErrorKind::ServerError | ErrorKind::Network | ErrorKind::Timeout
+    )
+}
+
+/// Delay after the given number of consecutive failed polls
+///
+/// Doubles the poll interval per failure up to `TAIL_MAX_BACKOFF`; a rate-limit
+/// reset sent by the server is waited out instead.
+fn tail_backoff(interval: Duration, failures: u32, error: &DatadogError) -> Duration {
+    let reset = error
+        .response()
+        .and_then(|r| r.rate_limit.as_ref())
+        .and_then(|r| r.reset)
+        .map(Duration::from_secs);
+    let backoff = interval
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(TAIL_MAX_BACKOFF);
+    reset.map_or(backoff, |reset| reset.max(interval))
+}
+
+/// Drop logs already in `seen` (id -> timestamp in ms) and order the rest oldest first
+fn take_unseen(logs: Vec<Value>, seen: &mut HashMap<String, i64>) -> Vec<Value> {
+    let mut fresh: Vec<(i64, Value)> = logs
+        .into_iter()
+        .filter_map(|log| {
+            let id = log["id"].as_str()?.to_string();
+            let ts = log["timestamp"]
+                .as_str()
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                .map_or_else(
+                    || chrono::Utc::now().timestamp_millis(),
+                    |t| t.timestamp_millis(),
+                );
+            seen.insert(id, ts).is_none().then_some((ts, log))
+        })
+        .collect();
+
+    fresh.sort_by_key(|(ts, _)| *ts);
+    fresh.into_iter().map(|(_, log)| log).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_group_by() {
+        assert_eq!(
+            LogsHandler::parse_group_by("service:10").unwrap(),
+            json!({"facet": "service", "limit": 10})
+        );
+        assert_eq!(
+            LogsHandler::parse_group_by("@http.status_code").unwrap(),
+            json!({"facet": "@http.status_code", "limit": null})
+        );
+        assert!(LogsHandler::parse_group_by("").is_err());
+        assert!(LogsHandler::parse_group_by("service:0").is_err());
+    }
+
+    #[test]
+    fn test_parse_compute() {
+        assert_eq!(
+            LogsHandler::parse_compute("count").unwrap(),
+            json!({"aggregation": "count"})
+        );
+        assert_eq!(
+            LogsHandler::parse_compute("pc95:@duration").unwrap(),
+            json!({"aggregation": "pc95", "metric": "@duration"})
+        );
+        assert!(LogsHandler::parse_compute("avg").is_err());
+        assert!(LogsHandler::parse_compute("count:@duration").is_err());
+        assert!(LogsHandler::parse_compute("p95:@duration").is_err());
+    }
+
+    #[test]
+    fn test_validate_timezone() {
+        for tz in [
+            "UTC",
+            "GMT",
+            "UTC+9",
+            "UTC-03:30",
+            "+09:00",
+            "Asia/Seoul",
+            "America/Argentina/Buenos_Aires",
+        ] {
+            assert!(LogsHandler::validate_timezone(tz).is_ok(), "{}", tz);
+        }
+        for tz in ["", "Seoul", "UTC+99", "Asia//Seoul", "KST 9"] {
+            assert!(LogsHandler::validate_timezone(tz).is_err(), "{}", tz);
+        }
+    }
+
+    #[test]
+    fn test_flatten_buckets() {
+        let totals = json!({"buckets": [
+            {"by": {"service": "api", "status": "error"}, "computes": {"c0": 12, "c1": 250.5}},
+            {"by": {"service": "web", "status": "info"}, "computes": {"c0": 3, "c1": 80.0}},
+        ]});
+        assert_eq!(
+            flatten_buckets(&totals),
+            vec![
+                json!({"service": "api", "status": "error", "c0": 12, "c1": 250.5}),
+                json!({"service": "web", "status": "info", "c0": 3, "c1": 80.0}),
+            ]
+        );
+
+        let series = json!({"buckets": [
+            {"by": {"service": "api"}, "computes": {
+                "c0": [{"time": "2024-01-01T01:00:00Z", "value": 4}, {"time": "2024-01-01T00:00:00Z", "value": 2}],
+                "c1": [{"time": "2024-01-01T00:00:00Z", "value": 9.5}],
+            }},
+        ]});
+        assert_eq!(
+            flatten_buckets(&series),
+            vec![
+                json!({"service": "api", "timestamp": "2024-01-01T00:00:00Z", "c0": 2, "c1": 9.5}),
+                json!({"service": "api", "timestamp": "2024-01-01T01:00:00Z", "c0": 4}),
+            ]
+        );
+
+        assert!(flatten_buckets(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_take_unseen_dedupes_and_orders() {
+        let mut seen = HashMap::new();
+        let first = vec![
+            json!({"id": "b", "timestamp": "2024-01-01T00:00:02Z"}),
+            json!({"id": "a", "timestamp": "2024-01-01T00:00:01Z"}),
+        ];
+        let ids = |logs: Vec<Value>| -> Vec<String> {
+            logs.iter()
+                .map(|l| l["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(ids(take_unseen(first, &mut seen)), vec!["a", "b"]);
+
+        let overlapping = vec![
+            json!({"id": "c", "timestamp": "2024-01-01T00:00:03Z"}),
+            json!({"id": "b", "timestamp": "2024-01-01T00:00:02Z"}),
+        ];
+        assert_eq!(ids(take_unseen(overlapping, &mut seen)), vec!["c"]);
+        assert_eq!(seen.len(), 3);
+    }
+
     #[test]
     fn test_time_handler_trait() {
         let handler = LogsHandler;
@@ -282,4 +706,110 @@ mod tests {
         assert_eq!(handler.filter_tags(&tags, "env:").len(), 1);
         assert_eq!(handler.filter_tags(&tags, "").len(), 0);
     }
+
+    #[test]
+    fn test_tail_backoff() {
+        let interval = Duration::from_secs(5);
+        let server_error = DatadogError::HttpError(Box::new(crate::error::ApiErrorResponse::new(
+            503, "/api", "", None,
+        )));
+        assert_eq!(tail_backoff(interval, 1, &server_error), interval);
+        assert_eq!(
+            tail_backoff(interval, 3, &server_error),
+            Duration::from_secs(20)
+        );
+        assert_eq!(tail_backoff(interval, 40, &server_error), TAIL_MAX_BACKOFF);
+
+        let rate_limited =
+            DatadogError::RateLimitError(Box::new(crate::error::ApiErrorResponse::new(
+                429,
+                "/api",
+                "",
+                Some(crate::error::RateLimitInfo {
+                    reset: Some(90),
+                    ..Default::default()
+                }),
+            )));
+        assert!(is_transient(&rate_limited));
+        assert_eq!(
+            tail_backoff(interval, 1, &rate_limited),
+            Duration::from_secs(90)
+        );
+        assert!(!is_transient(&DatadogError::InvalidInput(
+            "bad query".into()
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_tail_keeps_polling_after_transient_error() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/logs/events/search"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/logs/events/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{
+                    "id": "AAA",
+                    "attributes": {"timestamp": chrono::Utc::now().to_rfc3339(), "message": "up"},
+                }],
+            })))
+            .mount(&server)
+            .await;
+
+        let client = DatadogClient::new("key".to_string(), "app".to_string(), None, 5, 0, None)
+            .unwrap()
+            .with_base_url(&server.uri());
+        let mut retries = Vec::new();
+        let mut received = Vec::new();
+
+        // Returning an error from on_logs is the only way to end the loop here
+        let result = LogsHandler::tail(
+            Arc::new(client),
+            &json!({"query": "*", "from": "5 minutes ago", "interval": 1}),
+            |logs| {
+                received.extend(logs);
+                Err(DatadogError::ApiError("stop".into()))
+            },
+            |error, delay| retries.push((error.status(), delay)),
+        )
+        .await;
+
+        assert!(matches!(result, Err(DatadogError::ApiError(_))));
+        assert_eq!(retries, vec![(Some(503), Duration::from_secs(1))]);
+        assert_eq!(received.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tail_stops_on_non_retryable_error() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/logs/events/search"))
+            .respond_with(ResponseTemplate::new(400).set_body_string(r#"{"errors":["bad query"]}"#))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = DatadogClient::new("key".to_string(), "app".to_string(), None, 5, 0, None)
+            .unwrap()
+            .with_base_url(&server.uri());
+        let result = LogsHandler::tail(
+            Arc::new(client),
+            &json!({"query": "*", "interval": 1}),
+            |_| Ok(()),
+            |_, _| panic!("a 400 must not be retried"),
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().status(), Some(400));
+    }
 }
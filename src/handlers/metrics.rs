@@ -2,11 +2,113 @@ use serde_json::{Value, json};
 use std::sync::Arc;
 
 use crate::datadog::DatadogClient;
-use crate::error::Result;
+use crate::datadog::models::Unit;
+use crate::error::{DatadogError, Result};
 use crate::handlers::common::{ResponseFormatter, TimeHandler, TimeParams};
 
 pub struct MetricsHandler;
 
+/// Space aggregators accepted by `/api/v2/query/scalar`
+pub const SCALAR_AGGREGATORS: &[&str] = &["avg", "max", "min", "sum", "last", "percentile"];
+
+/// Statistics computed per series by `--summary`
+pub const SUMMARY_STATS: [&str; 11] = [
+    "count", "min", "max", "mean", "last", "sum", "stddev", "p50", "p90", "p95", "p99",
+];
+
+/// Points aimed for when `--compare` has to pick a rollup for a query without `max_points`
+const COMPARE_MAX_POINTS: usize = 300;
+
+/// Baseline points shifted forward by the `--compare` offset, sorted by timestamp
+struct Baseline {
+    points: Vec<(i64, Option<f64>)>,
+    tolerance_ms: u64,
+}
+
+impl Baseline {
+    fn new(
+        points: impl IntoIterator<Item = (i64, Option<f64>)>,
+        offset_ms: i64,
+        interval_ms: i64,
+    ) -> Self {
+        let mut points: Vec<_> = points
+            .into_iter()
+            .map(|(t, v)| (t.saturating_add(offset_ms), v))
+            .collect();
+        points.sort_by_key(|(t, _)| *t);
+        Self {
+            points,
+            tolerance_ms: interval_ms.unsigned_abs() / 2,
+        }
+    }
+
+    /// Value of the point nearest to `timestamp`, if it lies within half an interval
+    ///
+    /// Nearest rather than exact matching keeps points paired when the offset is not
+    /// a multiple of the interval or the two windows were bucketed slightly apart.
+    fn at(&self, timestamp: i64) -> Option<f64> {
+        let i = self.points.partition_point(|(t, _)| *t < timestamp);
+        [i.checked_sub(1), Some(i)]
+            .into_iter()
+            .flatten()
+            .filter_map(|i| self.points.get(i))
+            .min_by_key(|(t, _)| t.abs_diff(timestamp))
+            .filter(|(t, _)| t.abs_diff(timestamp) <= self.tolerance_ms)
+            .and_then(|(_, v)| *v)
+    }
+}
+
+/// A `--assert` condition such as `< 250`, checked against every scalar value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Assertion {
+    pub op: &'static str,
+    pub threshold: f64,
+}
+
+impl Assertion {
+    const OPERATORS: [&'static str; 6] = ["<=", ">=", "==", "!=", "<", ">"];
+
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let op = Self::OPERATORS
+            .into_iter()
+            .find(|op| spec.starts_with(op))
+            .ok_or_else(|| {
+                DatadogError::InvalidInput(format!(
+                    "Invalid --assert '{}': expected OP NUMBER with OP one of {}",
+                    spec,
+                    Self::OPERATORS.join(" ")
+                ))
+            })?;
+        let threshold = spec[op.len()..].trim().parse::<f64>().map_err(|_| {
+            DatadogError::InvalidInput(format!(
+                "Invalid --assert '{}': '{}' is not a number",
+                spec,
+                spec[op.len()..].trim()
+            ))
+        })?;
+
+        Ok(Self { op, threshold })
+    }
+
+    pub fn holds(&self, value: f64) -> bool {
+        match self.op {
+            "<" => value < self.threshold,
+            "<=" => value <= self.threshold,
+            ">" => value > self.threshold,
+            ">=" => value >= self.threshold,
+            "==" => value == self.threshold,
+            _ => value != self.threshold,
+        }
+    }
+}
+
+impl std::fmt::Display for Assertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.op, self.threshold)
+    }
+}
+
 impl TimeHandler for MetricsHandler {}
 impl ResponseFormatter for MetricsHandler {}
 
@@ -61,6 +163,453 @@ impl MetricsHandler {
         format!("{}.rollup({}, {})", query, agg, interval)
     }
 
+    // Simplify unit - only include the first non-null unit
+    fn unit_summary(units: Option<&[Option<Unit>]>) -> Option<Value> {
+        let u = units?.iter().flatten().next()?;
+
+        let mut unit_obj = serde_json::Map::new();
+        unit_obj.insert("name".to_string(), json!(u.name));
+        unit_obj.insert("family".to_string(), json!(u.family));
+        if let Some(ref short_name) = u.short_name
+            && !short_name.is_empty()
+        {
+            unit_obj.insert("short_name".to_string(), json!(short_name));
+        }
+        Some(json!(unit_obj))
+    }
+
+    /// Summarize a series' non-null values, taken in point order
+    ///
+    /// Percentiles interpolate linearly between the closest ranks and `stddev` is
+    /// the population deviation. Every statistic but `count` is null for an empty series.
+    pub fn summarize(values: &[f64]) -> Value {
+        let Some(&last) = values.last() else {
+            let mut empty: serde_json::Map<String, Value> = SUMMARY_STATS
+                .iter()
+                .map(|s| (s.to_string(), Value::Null))
+                .collect();
+            empty.insert("count".to_string(), json!(0));
+            return Value::Object(empty);
+        };
+
+        let count = values.len() as f64;
+        let sum: f64 = values.iter().sum();
+        let mean = sum / count;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count;
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let percentile = |p: f64| {
+            let rank = p / 100.0 * (sorted.len() - 1) as f64;
+            let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+            sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+        };
+
+        json!({
+            "count": values.len(),
+            "min": sorted[0],
+            "max": sorted[sorted.len() - 1],
+            "mean": mean,
+            "last": last,
+            "sum": sum,
+            "stddev": variance.sqrt(),
+            "p50": percentile(50.0),
+            "p90": percentile(90.0),
+            "p95": percentile(95.0),
+            "p99": percentile(99.0),
+        })
+    }
+
+    /// Attach a `summary` to every series when `summary` or `summary_only` is set,
+    /// dropping the raw points for the latter
+    ///
+    /// With `compare`, a summary is always attached and each statistic becomes
+    /// `{current, baseline, change_pct}` over the aligned baseline points.
+    fn apply_summary(mut series: Vec<Value>, params: &Value) -> Vec<Value> {
+        let summary_only = params["summary_only"].as_bool().unwrap_or(false);
+        let compare = !params["compare"].is_null();
+        if !summary_only && !compare && !params["summary"].as_bool().unwrap_or(false) {
+            return series;
+        }
+
+        for s in series.iter_mut() {
+            let points = s["points"]["data"].as_array().cloned().unwrap_or_default();
+            let values_of =
+                |key: &str| -> Vec<f64> { points.iter().filter_map(|p| p[key].as_f64()).collect() };
+
+            let current = Self::summarize(&values_of("value"));
+            let summary = if compare {
+                Self::compare_summaries(&current, &Self::summarize(&values_of("baseline")))
+            } else {
+                current
+            };
+
+            if let Some(obj) = s.as_object_mut() {
+                if summary_only {
+                    obj.remove("points");
+                }
+                obj.insert("summary".to_string(), summary);
+            }
+        }
+        series
+    }
+
+    /// Pair every statistic of two summaries with its percent change
+    fn compare_summaries(current: &Value, baseline: &Value) -> Value {
+        let stats: serde_json::Map<String, Value> = SUMMARY_STATS
+            .iter()
+            .map(|stat| {
+                let (cur, base) = (&current[*stat], &baseline[*stat]);
+                let change = percent_change(cur.as_f64(), base.as_f64());
+                (
+                    stat.to_string(),
+                    json!({ "current": cur, "baseline": base, "change_pct": change }),
+                )
+            })
+            .collect();
+        Value::Object(stats)
+    }
+
+    /// Parse a `-q` spec: `name=query`, or a bare query named `query<position>`
+    pub fn parse_named_query(spec: &str, position: usize) -> Result<(String, String)> {
+        let (name, query) = match spec.split_once('=') {
+            Some((name, query)) if is_identifier(name.trim()) => (name.trim().to_string(), query),
+            _ => (format!("query{}", position), spec),
+        };
+
+        if query.trim().is_empty() {
+            return Err(DatadogError::InvalidInput(format!(
+                "Query '{}' is empty: expected NAME=QUERY, e.g. a=sum:requests{{*}}.as_count()",
+                name
+            )));
+        }
+        Ok((name, query.trim().to_string()))
+    }
+
+    /// Check that every variable in a formula names one of the queries
+    ///
+    /// Identifiers followed by `(` are functions such as `abs` or `top` and are skipped.
+    pub fn validate_formula(formula: &str, names: &[String]) -> Result<()> {
+        let chars: Vec<char> = formula.chars().collect();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            if c.is_ascii_digit() || c == '.' {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                    i += 1;
+                }
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                let ident: String = chars[start..i].iter().collect();
+                let is_call = chars[i..].iter().find(|c| !c.is_whitespace()) == Some(&'(');
+                if !is_call && !names.contains(&ident) {
+                    return Err(DatadogError::InvalidInput(format!(
+                        "Formula '{}' references unknown query '{}' (defined: {})",
+                        formula,
+                        ident,
+                        names.join(", ")
+                    )));
+                }
+            } else if c == '\'' || c == '"' {
+                // Quoted arguments, e.g. top(a, 10, 'mean', 'desc')
+                i += 1;
+                while i < chars.len() && chars[i] != c {
+                    i += 1;
+                }
+                i += 1;
+            } else {
+                i += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run named queries and formulas through the v2 timeseries API
+    ///
+    /// Every returned series shares the same timestamps, one series per formula
+    /// and group (or per query when no formula is given).
+    /// With `compare`, the shifted window is fetched at the current interval and each
+    /// series is paired with the baseline series of the same formula and group.
+    pub async fn formula_query(client: Arc<DatadogClient>, params: &Value) -> Result<Value> {
+        let handler = MetricsHandler;
+
+        let queries: Vec<(String, String)> = params["queries"]
+            .as_array()
+            .map(|qs| {
+                qs.iter()
+                    .filter_map(|q| Some((q["name"].as_str()?.into(), q["query"].as_str()?.into())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if queries.is_empty() {
+            return Err(DatadogError::InvalidInput(
+                "At least one query is required".to_string(),
+            ));
+        }
+
+        let names: Vec<String> = queries.iter().map(|(name, _)| name.clone()).collect();
+        if let Some(duplicate) = names
+            .iter()
+            .enumerate()
+            .find(|(i, n)| names[..*i].contains(n))
+        {
+            return Err(DatadogError::InvalidInput(format!(
+                "Query name '{}' is used more than once",
+                duplicate.1
+            )));
+        }
+
+        let formulas: Vec<String> = params["formulas"]
+            .as_array()
+            .map(|fs| {
+                fs.iter()
+                    .filter_map(|f| f.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for formula in &formulas {
+            Self::validate_formula(formula, &names)?;
+        }
+
+        let TimeParams::Timestamp { from, to } = handler.parse_time(params, 1)?;
+        let interval = params["interval"].as_i64();
+        let compare = params["compare"].as_i64();
+        let requested_ms = interval.map(|i| millis(i, "--interval")).transpose()?;
+        let offset_ms = compare.map(|o| millis(o, "--compare")).transpose()?;
+
+        let fetch = |from: i64, to: i64, interval_ms: Option<i64>| {
+            let (client, queries, formulas) = (&client, &queries, &formulas);
+            async move {
+                let (from_ms, to_ms) = (millis(from, "--from")?, millis(to, "--to")?);
+                let response = client
+                    .query_timeseries(from_ms, to_ms, interval_ms, queries, formulas)
+                    .await?;
+                if let Some(errors) = response.errors.as_ref().filter(|e| !e.is_empty()) {
+                    return Err(DatadogError::InvalidInput(errors.clone()));
+                }
+                Ok(response.data.map(|d| d.attributes))
+            }
+        };
+
+        let (series, times, values) = fetch(from, to, requested_ms)
+            .await?
+            .map(|a| (a.series, a.times, a.values))
+            .unwrap_or_default();
+
+        // The baseline reuses the interval the current window was bucketed with
+        let interval_ms = requested_ms.or_else(|| times.windows(2).map(|w| w[1] - w[0]).next());
+        let baseline = match compare {
+            Some(offset) => fetch(from - offset, to - offset, interval_ms).await?,
+            None => None,
+        };
+
+        let labels: Vec<&String> = if formulas.is_empty() {
+            names.iter().collect()
+        } else {
+            formulas.iter().collect()
+        };
+
+        let data: Vec<Value> = series
+            .iter()
+            .zip(values.iter())
+            .map(|(s, points)| {
+                let label = s.query_index.and_then(|i| labels.get(i)).copied();
+                let baseline_points = offset_ms.zip(baseline.as_ref()).map(|(offset_ms, b)| {
+                    let values = b
+                        .series
+                        .iter()
+                        .position(|bs| {
+                            bs.query_index == s.query_index && bs.group_tags == s.group_tags
+                        })
+                        .and_then(|i| b.values.get(i));
+                    let points = values
+                        .map(|vs| b.times.iter().copied().zip(vs.iter().copied()).collect())
+                        .unwrap_or_else(Vec::new);
+                    Baseline::new(points, offset_ms, interval_ms.unwrap_or_default())
+                });
+
+                let points: Vec<Value> = times
+                    .iter()
+                    .zip(points.iter())
+                    .map(|(t, v)| {
+                        let mut point = json!({
+                            "timestamp": crate::utils::format_timestamp(t / 1000),
+                            "value": v,
+                        });
+                        if let Some(ref baseline_points) = baseline_points {
+                            let base = baseline_points.at(*t);
+                            point["baseline"] = json!(base);
+                            point["change_pct"] = json!(percent_change(*v, base));
+                        }
+                        point
+                    })
+                    .collect();
+
+                let mut series_obj = serde_json::Map::new();
+                series_obj.insert("name".to_string(), json!(label));
+                series_obj.insert(
+                    "group_tags".to_string(),
+                    json!(s.group_tags.clone().unwrap_or_default()),
+                );
+                series_obj.insert(
+                    "points".to_string(),
+                    json!({ "count": points.len(), "data": points }),
+                );
+                if let Some(unit) = Self::unit_summary(s.unit.as_deref()) {
+                    series_obj.insert("unit".to_string(), unit);
+                }
+                json!(series_obj)
+            })
+            .collect();
+        let data = Self::apply_summary(data, params);
+
+        let mut meta = json!({
+            "queries": queries
+                .iter()
+                .map(|(name, query)| (name.clone(), json!(query)))
+                .collect::<serde_json::Map<_, _>>(),
+            "formulas": formulas,
+            "from": crate::utils::format_timestamp(from),
+            "to": crate::utils::format_timestamp(to),
+            "interval": interval,
+            "series_count": data.len(),
+        });
+        if let Some(offset) = compare {
+            meta["compare"] = compare_meta(offset, from, to);
+        }
+
+        Ok(handler.format_list(json!(data), None, Some(meta)))
+    }
+
+    /// Reduce a query to one number per group through the v2 scalar API
+    ///
+    /// With `assert`, every row records whether it satisfied the condition and
+    /// `meta.assertion.passed` holds only when all rows did; a missing value fails.
+    pub async fn scalar(client: Arc<DatadogClient>, params: &Value) -> Result<Value> {
+        let handler = MetricsHandler;
+
+        let query = params["query"]
+            .as_str()
+            .ok_or_else(|| DatadogError::InvalidInput("Missing 'query' parameter".to_string()))?;
+        let aggregator = params["aggregator"].as_str().unwrap_or("avg");
+        if !SCALAR_AGGREGATORS.contains(&aggregator) {
+            return Err(DatadogError::InvalidInput(format!(
+                "Unknown aggregator '{}' (expected one of {})",
+                aggregator,
+                SCALAR_AGGREGATORS.join(", ")
+            )));
+        }
+        let assertion = params["assert"]
+            .as_str()
+            .map(Assertion::parse)
+            .transpose()?;
+
+        let TimeParams::Timestamp { from, to } = handler.parse_time(params, 1)?;
+
+        let (from_ms, to_ms) = (millis(from, "--from")?, millis(to, "--to")?);
+        let response = client
+            .query_scalar(from_ms, to_ms, query, aggregator)
+            .await?;
+
+        if let Some(errors) = response.errors.as_ref().filter(|e| !e.is_empty()) {
+            return Err(DatadogError::InvalidInput(errors.clone()));
+        }
+
+        let columns = response
+            .data
+            .map(|d| d.attributes.columns)
+            .unwrap_or_default();
+        let numbers = columns.iter().find(|c| c.column_type == "number");
+        let unit = numbers
+            .and_then(|c| c.meta.as_ref())
+            .and_then(|m| Self::unit_summary(m.unit.as_deref()));
+
+        let data: Vec<Value> = numbers
+            .map(|c| c.values.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let group_tags: Vec<Value> = columns
+                    .iter()
+                    .filter(|c| c.column_type == "group")
+                    .filter_map(|c| c.values.get(i)?.as_array().cloned())
+                    .flatten()
+                    .collect();
+
+                let mut row = serde_json::Map::new();
+                row.insert("group_tags".to_string(), json!(group_tags));
+                row.insert("value".to_string(), value.clone());
+                if let Some(ref unit) = unit {
+                    row.insert("unit".to_string(), unit.clone());
+                }
+                if let Some(assertion) = assertion {
+                    let passed = value.as_f64().is_some_and(|v| assertion.holds(v));
+                    row.insert("passed".to_string(), json!(passed));
+                }
+                json!(row)
+            })
+            .collect();
+
+        let mut meta = json!({
+            "query": query,
+            "aggregator": aggregator,
+            "from": crate::utils::format_timestamp(from),
+            "to": crate::utils::format_timestamp(to),
+        });
+        if let Some(assertion) = assertion {
+            let passed = !data.is_empty() && data.iter().all(|row| row["passed"] == true);
+            meta["assertion"] = json!({ "condition": assertion.to_string(), "passed": passed });
+        }
+
+        Ok(handler.format_list(json!(data), None, Some(meta)))
+    }
+
+    /// Turn a failed `--assert` into an error so the process exits non-zero
+    pub fn ensure_assertion(result: &Value) -> Result<()> {
+        let assertion = &result["meta"]["assertion"];
+        if assertion.is_null() || assertion["passed"] == true {
+            return Ok(());
+        }
+
+        let rows = result["data"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let failures: Vec<String> = rows
+            .iter()
+            .filter(|row| row["passed"] != true)
+            .map(|row| {
+                let tags: Vec<&str> = row["group_tags"]
+                    .as_array()
+                    .map(|t| t.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+                let value = match &row["value"] {
+                    Value::Null => "no data".to_string(),
+                    v => v.to_string(),
+                };
+                if tags.is_empty() {
+                    value
+                } else {
+                    format!("{} = {}", tags.join(","), value)
+                }
+            })
+            .collect();
+
+        let condition = assertion["condition"].as_str().unwrap_or_default();
+        let message = if rows.is_empty() {
+            format!("no data to check against '{}'", condition)
+        } else {
+            format!("expected {}, got {}", condition, failures.join("; "))
+        };
+        Err(DatadogError::AssertionFailed(message))
+    }
+
     pub async fn query(client: Arc<DatadogClient>, params: &Value) -> Result<Value> {
         let handler = MetricsHandler;
 
@@ -80,27 +629,66 @@ impl MetricsHandler {
 
         // Get max_points parameter and apply rollup at API level
         let max_points = params["max_points"].as_i64().map(|p| p as usize);
+        let compare = params["compare"].as_i64();
+        let offset_ms = compare.map(|o| millis(o, "--compare")).transpose()?;
         let mut applied_rollup = false;
+        let mut rollup_interval = None;
 
-        if let Some(max) = max_points {
+        // A compared query always gets an explicit rollup so both windows share their buckets
+        if max_points.is_some() || compare.is_some() {
+            let max = max_points.unwrap_or(COMPARE_MAX_POINTS);
             let interval = Self::calculate_rollup_interval(from_ts, to_ts, max);
             query = Self::add_rollup_to_query(&query, interval);
             applied_rollup = true;
+            rollup_interval = Some(interval);
         }
 
         let response = client.query_metrics(&query, from_ts, to_ts).await?;
 
+        // Same query over the window shifted back by the offset
+        let baseline = match compare {
+            Some(offset) => Some(
+                client
+                    .query_metrics(&query, from_ts - offset, to_ts - offset)
+                    .await?,
+            ),
+            None => None,
+        };
+
         let series = response.series.iter().map(|s| {
+            let baseline_points = offset_ms.zip(baseline.as_ref()).map(|(offset_ms, b)| {
+                let points = b
+                    .series
+                    .iter()
+                    .find(|b| b.metric == s.metric && b.scope == s.scope)
+                    .and_then(|b| b.pointlist.as_ref())
+                    .map(|pointlist| {
+                        pointlist
+                            .iter()
+                            .filter_map(|p| Some((p.first().copied()?? as i64, *p.get(1)?)))
+                            .collect()
+                    })
+                    .unwrap_or_else(Vec::new);
+                let interval = s.interval.or(rollup_interval).unwrap_or_default();
+                Baseline::new(points, offset_ms, interval.saturating_mul(1000))
+            });
+
             let points_data = if let Some(ref pointlist) = s.pointlist {
                 json!({
                     "count": pointlist.len(),
                     "data": pointlist.iter().map(|p| {
                         if p.len() >= 2 {
-                            json!({
+                            let mut point = json!({
                                 "timestamp": p[0].map(|t| crate::utils::format_timestamp(t as i64 / 1000))
                                     .unwrap_or_else(|| "N/A".to_string()),
                                 "value": p[1]
-                            })
+                            });
+                            if let Some(ref baseline_points) = baseline_points {
+                                let base = p[0].and_then(|t| baseline_points.at(t as i64));
+                                point["baseline"] = json!(base);
+                                point["change_pct"] = json!(percent_change(p[1], base));
+                            }
+                            point
                         } else {
                             json!({
                                 "timestamp": "N/A",
@@ -129,23 +717,13 @@ impl MetricsHandler {
             if let Some(interval) = s.interval {
                 series_obj.insert("interval".to_string(), json!(interval));
             }
-            if let Some(ref unit) = s.unit {
-                // Simplify unit - only include the first non-null unit
-                if let Some(first_unit) = unit.iter().find(|u| u.is_some())
-                    && let Some(u) = first_unit {
-                        let mut unit_obj = serde_json::Map::new();
-                        unit_obj.insert("name".to_string(), json!(u.name));
-                        unit_obj.insert("family".to_string(), json!(u.family));
-                        if let Some(ref short_name) = u.short_name
-                            && !short_name.is_empty() {
-                                unit_obj.insert("short_name".to_string(), json!(short_name));
-                            }
-                        series_obj.insert("unit".to_string(), json!(unit_obj));
-                    }
+            if let Some(unit) = Self::unit_summary(s.unit.as_deref()) {
+                series_obj.insert("unit".to_string(), unit);
             }
 
             json!(series_obj)
         }).collect::<Vec<_>>();
+        let series = Self::apply_summary(series, params);
 
         // Build optimized meta - only include meaningful fields
         let mut meta = serde_json::Map::new();
@@ -181,6 +759,10 @@ impl MetricsHandler {
             meta.insert("group_by".to_string(), json!(group_by));
         }
 
+        if let Some(offset) = compare {
+            meta.insert("compare".to_string(), compare_meta(offset, from_ts, to_ts));
+        }
+
         if applied_rollup {
             meta.insert("rollup_applied".to_string(), json!(true));
             if let Some(max) = max_points {
@@ -192,6 +774,40 @@ impl MetricsHandler {
     }
 }
 
+/// The `--compare` offset and the baseline window it selects
+fn compare_meta(offset: i64, from: i64, to: i64) -> Value {
+    json!({
+        "offset": crate::utils::format_duration(offset),
+        "baseline_from": crate::utils::format_timestamp(from - offset),
+        "baseline_to": crate::utils::format_timestamp(to - offset),
+    })
+}
+
+/// Convert a duration given in seconds to the milliseconds the APIs take
+fn millis(seconds: i64, flag: &str) -> Result<i64> {
+    seconds
+        .checked_mul(1000)
+        .ok_or_else(|| DatadogError::InvalidInput(format!("{} of {}s is too large", flag, seconds)))
+}
+
+/// Change from `baseline` to `current` in percent; undefined for a zero baseline
+fn percent_change(current: Option<f64>, baseline: Option<f64>) -> Option<f64> {
+    match (current, baseline) {
+        (Some(current), Some(baseline)) if baseline != 0.0 => {
+            Some((current - baseline) / baseline.abs() * 100.0)
+        }
+        _ => None,
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -411,4 +1027,404 @@ mod tests {
             86400
         );
     }
+
+    #[test]
+    fn test_parse_named_query() {
+        assert_eq!(
+            MetricsHandler::parse_named_query("a=sum:requests{status:5xx}.as_count()", 1).unwrap(),
+            (
+                "a".to_string(),
+                "sum:requests{status:5xx}.as_count()".to_string()
+            )
+        );
+        // `=` inside the query is not a name separator
+        assert_eq!(
+            MetricsHandler::parse_named_query("avg:cpu{host=web}", 2).unwrap(),
+            ("query2".to_string(), "avg:cpu{host=web}".to_string())
+        );
+        assert!(MetricsHandler::parse_named_query("a=", 1).is_err());
+    }
+
+    #[test]
+    fn test_validate_formula() {
+        let names = vec!["a".to_string(), "b".to_string()];
+        assert!(MetricsHandler::validate_formula("100*a/b", &names).is_ok());
+        assert!(MetricsHandler::validate_formula("abs(a) - 1.5e3", &names).is_ok());
+        assert!(MetricsHandler::validate_formula("top(a, 10, 'mean', 'desc')", &names).is_ok());
+        assert!(MetricsHandler::validate_formula("a / c", &names).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_formula_query_aligns_series() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/query/timeseries"))
+            .and(body_partial_json(json!({
+                "data": {"attributes": {"formulas": [{"formula": "100*a/b"}], "interval": 60000}}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {"attributes": {
+                    "series": [{"group_tags": ["service:web"], "query_index": 0}],
+                    "times": [1700000000000i64, 1700000060000i64],
+                    "values": [[1.5, null]]
+                }}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = DatadogClient::new("key".to_string(), "app".to_string(), None, 5, 0, None)
+            .unwrap()
+            .with_base_url(&server.uri());
+        let params = json!({
+            "queries": [
+                {"name": "a", "query": "sum:requests{status:5xx}.as_count()"},
+                {"name": "b", "query": "sum:requests{*}.as_count()"}
+            ],
+            "formulas": ["100*a/b"],
+            "from": "1700000000",
+            "to": "1700000120",
+            "interval": 60,
+        });
+
+        let result = MetricsHandler::formula_query(Arc::new(client), &params)
+            .await
+            .unwrap();
+        let series = &result["data"][0];
+        assert_eq!(series["name"], "100*a/b");
+        assert_eq!(series["group_tags"], json!(["service:web"]));
+        assert_eq!(series["points"]["count"], 2);
+        assert_eq!(series["points"]["data"][0]["value"], 1.5);
+        assert_eq!(series["points"]["data"][1]["value"], Value::Null);
+        assert_eq!(result["meta"]["queries"]["b"], "sum:requests{*}.as_count()");
+    }
+
+    #[test]
+    fn test_assertion_parse_and_holds() {
+        let lt = Assertion::parse("< 250").unwrap();
+        assert_eq!(
+            lt,
+            Assertion {
+                op: "<",
+                threshold: 250.0
+            }
+        );
+        assert!(lt.holds(249.9));
+        assert!(!lt.holds(250.0));
+
+        let ge = Assertion::parse(">=0.5").unwrap();
+        assert_eq!(ge.op, ">=");
+        assert!(ge.holds(0.5));
+        assert_eq!(ge.to_string(), ">= 0.5");
+
+        assert!(Assertion::parse("250").is_err());
+        assert!(Assertion::parse("< fast").is_err());
+    }
+
+    #[test]
+    fn test_ensure_assertion() {
+        let result = |rows: Value, passed: bool| json!({"data": rows, "meta": {"assertion": {"condition": "< 250", "passed": passed}}});
+
+        assert!(MetricsHandler::ensure_assertion(&json!({"data": [], "meta": {}})).is_ok());
+        assert!(
+            MetricsHandler::ensure_assertion(&result(
+                json!([{"group_tags": [], "value": 10.0, "passed": true}]),
+                true
+            ))
+            .is_ok()
+        );
+
+        let error = MetricsHandler::ensure_assertion(&result(
+            json!([
+                {"group_tags": ["service:web"], "value": 10.0, "passed": true},
+                {"group_tags": ["service:api"], "value": 312.0, "passed": false},
+                {"group_tags": ["service:db"], "value": null, "passed": false},
+            ]),
+            false,
+        ))
+        .unwrap_err();
+        assert_eq!(error.exit_code(), 9);
+        assert_eq!(
+            error.to_string(),
+            "Assertion failed: expected < 250, got service:api = 312.0; service:db = no data"
+        );
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = MetricsHandler::summarize(&[4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(summary["count"], 4);
+        assert_eq!(summary["min"], 1.0);
+        assert_eq!(summary["max"], 4.0);
+        assert_eq!(summary["mean"], 2.5);
+        assert_eq!(summary["last"], 2.0);
+        assert_eq!(summary["sum"], 10.0);
+        assert_eq!(summary["stddev"], 1.25f64.sqrt());
+        assert_eq!(summary["p50"], 2.5);
+        assert!((summary["p90"].as_f64().unwrap() - 3.7).abs() < 1e-9);
+        assert!(
+            SUMMARY_STATS
+                .iter()
+                .all(|stat| summary.get(*stat).is_some())
+        );
+
+        let empty = MetricsHandler::summarize(&[]);
+        assert_eq!(empty["count"], 0);
+        assert_eq!(empty["p99"], Value::Null);
+    }
+
+    #[test]
+    fn test_apply_summary_skips_null_points() {
+        let series = vec![json!({
+            "metric": "cpu",
+            "points": {"count": 3, "data": [
+                {"timestamp": "t1", "value": 1.0},
+                {"timestamp": "t2", "value": null},
+                {"timestamp": "t3", "value": 3.0},
+            ]}
+        })];
+
+        let unchanged = MetricsHandler::apply_summary(series.clone(), &json!({}));
+        assert!(unchanged[0].get("summary").is_none());
+
+        let summarized = MetricsHandler::apply_summary(series.clone(), &json!({"summary": true}));
+        assert_eq!(summarized[0]["summary"]["count"], 2);
+        assert_eq!(summarized[0]["summary"]["last"], 3.0);
+        assert_eq!(summarized[0]["points"]["count"], 3);
+
+        let only = MetricsHandler::apply_summary(series, &json!({"summary_only": true}));
+        assert!(only[0].get("points").is_none());
+        assert_eq!(only[0]["summary"]["mean"], 2.0);
+    }
+
+    #[test]
+    fn test_percent_change() {
+        assert_eq!(percent_change(Some(12.0), Some(10.0)), Some(20.0));
+        assert_eq!(percent_change(Some(5.0), Some(-10.0)), Some(150.0));
+        assert_eq!(percent_change(Some(5.0), Some(0.0)), None);
+        assert_eq!(percent_change(None, Some(10.0)), None);
+        assert_eq!(percent_change(Some(5.0), None), None);
+    }
+
+    #[test]
+    fn test_apply_summary_compares_with_baseline() {
+        let series = vec![json!({
+            "metric": "cpu",
+            "points": {"count": 2, "data": [
+                {"timestamp": "t1", "value": 12.0, "baseline": 10.0, "change_pct": 20.0},
+                {"timestamp": "t2", "value": 18.0, "baseline": 10.0, "change_pct": 80.0},
+            ]}
+        })];
+
+        let compared = MetricsHandler::apply_summary(series, &json!({"compare": 86400}));
+        let summary = &compared[0]["summary"];
+        assert_eq!(
+            summary["mean"],
+            json!({"current": 15.0, "baseline": 10.0, "change_pct": 50.0})
+        );
+        assert_eq!(
+            summary["stddev"],
+            json!({"current": 3.0, "baseline": 0.0, "change_pct": null})
+        );
+        assert_eq!(compared[0]["points"]["count"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_formula_query_rejects_oversized_interval() {
+        let client = DatadogClient::new("key".to_string(), "app".to_string(), None, 5, 0, None)
+            .unwrap()
+            .with_base_url("http://127.0.0.1:1");
+        let params = json!({
+            "queries": [{"name": "a", "query": "avg:cpu{*}"}],
+            "interval": i64::MAX / 10,
+        });
+
+        let err = MetricsHandler::formula_query(Arc::new(client), &params)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DatadogError::InvalidInput(_)));
+        assert!(err.to_string().contains("--interval"));
+
+        let client = DatadogClient::new("key".to_string(), "app".to_string(), None, 5, 0, None)
+            .unwrap()
+            .with_base_url("http://127.0.0.1:1");
+        let params = json!({
+            "queries": [{"name": "a", "query": "avg:cpu{*}"}],
+            "to": "99999999999999999",
+        });
+        let err = MetricsHandler::formula_query(Arc::new(client), &params)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DatadogError::InvalidInput(_)));
+        assert!(err.to_string().contains("--to"));
+    }
+
+    #[tokio::test]
+    async fn test_scalar_rejects_oversized_time_range() {
+        let client = DatadogClient::new("key".to_string(), "app".to_string(), None, 5, 0, None)
+            .unwrap()
+            .with_base_url("http://127.0.0.1:1");
+        let params = json!({
+            "query": "avg:cpu{*}",
+            "from": "99999999999999999",
+            "assert": "< 90",
+        });
+
+        let err = MetricsHandler::scalar(Arc::new(client), &params)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DatadogError::InvalidInput(_)));
+        assert!(err.to_string().contains("--from"));
+        assert_eq!(err.exit_code(), 2);
+    }
+
+    #[test]
+    fn test_baseline_pairs_nearest_point() {
+        // Offset of 90s against a 60s interval: no shifted point lands exactly
+        let baseline = Baseline::new(
+            [(0, Some(1.0)), (60_000, Some(2.0)), (120_000, None)],
+            90_000,
+            60_000,
+        );
+        assert_eq!(baseline.at(60_000), Some(1.0));
+        assert_eq!(baseline.at(120_000), Some(1.0));
+        assert_eq!(baseline.at(160_000), Some(2.0));
+        assert_eq!(baseline.at(210_000), None);
+        assert_eq!(baseline.at(0), None);
+        assert_eq!(baseline.at(400_000), None);
+    }
+
+    #[tokio::test]
+    async fn test_query_compare_aligns_shifted_window() {
+        use wiremock::matchers::{method, path, query_param, query_param_contains};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let window = |from: i64, points: Value| {
+            json!({
+                "status": "ok", "res_type": "time_series", "from_date": from * 1000,
+                "to_date": (from + 120) * 1000, "query": "avg:cpu{*}.rollup(avg, 60)",
+                "series": [{
+                    "metric": "cpu", "display_name": null, "unit": null, "pointlist": points,
+                    "scope": "*", "expression": "avg:cpu{*}", "tag_set": [], "aggr": "avg",
+                    "interval": 60, "length": 2, "start": null, "end": null,
+                    "attributes": null, "query_index": 0
+                }]
+            })
+        };
+
+        let server = MockServer::start().await;
+        // Both windows carry the same explicit rollup; the baseline buckets sit 10s off
+        for (from, points) in [
+            (
+                1_700_086_400i64,
+                json!([[1_700_086_400_000i64, 10.0], [1_700_086_460_000i64, 15.0]]),
+            ),
+            (
+                1_700_000_000i64,
+                json!([[1_700_000_010_000i64, 5.0], [1_700_000_070_000i64, 10.0]]),
+            ),
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/api/v1/query"))
+                .and(query_param("from", from.to_string()))
+                .and(query_param_contains("query", ".rollup(avg, 60)"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(window(from, points)))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+
+        let client = DatadogClient::new("key".to_string(), "app".to_string(), None, 5, 0, None)
+            .unwrap()
+            .with_base_url(&server.uri());
+        let params = json!({
+            "query": "avg:cpu{*}",
+            "from": "1700086400",
+            "to": "1700086520",
+            "compare": 86400,
+        });
+
+        let result = MetricsHandler::query(Arc::new(client), &params)
+            .await
+            .unwrap();
+        let points = &result["data"][0]["points"]["data"];
+        assert_eq!(points[0]["baseline"], 5.0);
+        assert_eq!(points[0]["change_pct"], 100.0);
+        assert_eq!(points[1]["baseline"], 10.0);
+        assert_eq!(points[1]["change_pct"], 50.0);
+        let mean = &result["data"][0]["summary"]["mean"];
+        assert_eq!(
+            (mean["current"].clone(), mean["baseline"].clone()),
+            (json!(12.5), json!(7.5))
+        );
+        assert_eq!(result["meta"]["compare"]["offset"], "1d");
+    }
+
+    #[tokio::test]
+    async fn test_formula_query_compare_matches_groups() {
+        use wiremock::matchers::{body_partial_json, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/query/timeseries"))
+            .and(body_partial_json(
+                json!({"data": {"attributes": {"from": 1700086400000i64}}}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {"attributes": {
+                    "series": [{"group_tags": ["service:web"], "query_index": 0}],
+                    "times": [1700086400000i64, 1700086460000i64],
+                    "values": [[4.0, 6.0]]
+                }}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // The baseline reuses the current 60s step and lists its groups in another order
+        Mock::given(method("POST"))
+            .and(path("/api/v2/query/timeseries"))
+            .and(body_partial_json(json!({
+                "data": {"attributes": {"from": 1700000000000i64, "interval": 60000}}
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": {"attributes": {
+                    "series": [
+                        {"group_tags": ["service:api"], "query_index": 0},
+                        {"group_tags": ["service:web"], "query_index": 0}
+                    ],
+                    "times": [1700000000000i64, 1700000060000i64],
+                    "values": [[100.0, 100.0], [2.0, null]]
+                }}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = DatadogClient::new("key".to_string(), "app".to_string(), None, 5, 0, None)
+            .unwrap()
+            .with_base_url(&server.uri());
+        let params = json!({
+            "queries": [{"name": "a", "query": "sum:requests{*} by {service}"}],
+            "from": "1700086400",
+            "to": "1700086520",
+            "compare": 86400,
+        });
+
+        let result = MetricsHandler::formula_query(Arc::new(client), &params)
+            .await
+            .unwrap();
+        let points = &result["data"][0]["points"]["data"];
+        assert_eq!(points[0]["baseline"], 2.0);
+        assert_eq!(points[0]["change_pct"], 100.0);
+        assert_eq!(points[1]["baseline"], Value::Null);
+        assert_eq!(
+            result["meta"]["compare"]["baseline_from"],
+            crate::utils::format_timestamp(1_700_000_000)
+        );
+        assert_eq!(result["data"][0]["summary"]["last"]["baseline"], 2.0);
+    }
 }
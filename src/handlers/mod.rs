@@ -1,3 +1,4 @@
+pub mod auth;
 pub mod common;
 pub mod dashboards;
 pub mod events;
@@ -5,6 +6,7 @@ pub mod hosts;
 pub mod logs;
 pub mod metrics;
 pub mod monitors;
+pub mod pagination;
 pub mod rum;
 pub mod services;
 pub mod spans;
@@ -39,7 +39,8 @@ impl MonitorsHandler {
             })
             .collect();
 
-        let pagination = PaginationInfo::single_page(data.len(), page_size as usize);
+        let pagination =
+            PaginationInfo::from_page_open(page as usize, page_size as usize, data.len());
 
         Ok(handler.format_list(json!(data), Some(serde_json::to_value(pagination)?), None))
     }
@@ -64,6 +65,7 @@ impl MonitorsHandler {
             "modified": m.modified,
             "overall_state": m.overall_state,
             "priority": m.priority,
+            "creator": m.creator,
             "options": m.options.as_ref().map(|o| {
                 let mut opts = json!({
                     "thresholds": o.thresholds,
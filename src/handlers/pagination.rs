@@ -0,0 +1,351 @@
+use serde_json::{Value, json};
+use std::future::Future;
+
+use crate::error::Result;
+use crate::handlers::common::PaginationInfo;
+use crate::utils::parse_time;
+
+/// Largest page requested while following cursors; logs, spans and RUM all accept it
+pub const MAX_CURSOR_PAGE_LIMIT: i32 = 1000;
+
+/// Follow `pagination.next_cursor` until the results run out or `max_results` is reached
+///
+/// `fetch` runs one handler call for the given params and each page is handed
+/// to `on_page` as soon as it arrives. Returns the pagination of the whole run,
+/// with `next_cursor` set when it stopped early so it can be resumed.
+pub async fn follow_cursor<F, Fut>(
+    params: &Value,
+    max_results: Option<usize>,
+    fetch: F,
+    mut on_page: impl FnMut(Value) -> Result<()>,
+) -> Result<PaginationInfo>
+where
+    F: Fn(Value) -> Fut,
+    Fut: Future<Output = Result<Value>>,
+{
+    let mut params = pin_time_range(params)?;
+    let page_limit = params["limit"]
+        .as_i64()
+        .map(|l| l as i32)
+        .unwrap_or(MAX_CURSOR_PAGE_LIMIT);
+
+    let mut fetched = 0;
+    let mut pages: usize = 0;
+
+    loop {
+        let remaining = max_results.map(|max| max.saturating_sub(fetched));
+        if remaining == Some(0) {
+            break;
+        }
+        params["limit"] = json!(remaining.map_or(page_limit, |r| page_limit.min(r as i32)));
+
+        let mut page = fetch(params.clone()).await?;
+        pages += 1;
+
+        let next_cursor = page["pagination"]["next_cursor"]
+            .as_str()
+            .map(|s| s.to_string());
+        let count = page["data"].as_array().map_or(0, |d| d.len());
+        fetched += count;
+
+        if let Some(data) = page.get_mut("data").and_then(Value::as_array_mut)
+            && let Some(r) = remaining
+        {
+            data.truncate(r);
+        }
+        on_page(page)?;
+
+        match next_cursor {
+            Some(cursor) if count > 0 => params["cursor"] = json!(cursor),
+            _ => {
+                params["cursor"] = Value::Null;
+                break;
+            }
+        }
+    }
+
+    let next_cursor = params["cursor"].as_str().map(|s| s.to_string());
+    let mut pagination = PaginationInfo::from_cursor(
+        max_results.map_or(fetched, |max| fetched.min(max)),
+        page_limit as usize,
+        next_cursor,
+    );
+    pagination.page = pages.saturating_sub(1);
+
+    Ok(pagination)
+}
+
+/// Pages fetched at once while walking offsets
+pub const OFFSET_CONCURRENCY: usize = 4;
+
+/// Default cap on results when walking offsets without `max_results`
+pub const MAX_OFFSET_RESULTS: usize = 10_000;
+
+/// How an offset-paginated handler takes its position
+#[derive(Debug, Clone, Copy)]
+pub enum OffsetParams {
+    /// Item offset and page size, e.g. `start`/`count`
+    Start {
+        start: &'static str,
+        count: &'static str,
+    },
+    /// Page number and page size, e.g. `page`/`page_size`
+    Page {
+        page: &'static str,
+        size: &'static str,
+    },
+}
+
+impl OffsetParams {
+    fn size_key(self) -> &'static str {
+        match self {
+            Self::Start { count, .. } => count,
+            Self::Page { size, .. } => size,
+        }
+    }
+
+    fn offset(self, params: &Value, size: usize) -> usize {
+        match self {
+            Self::Start { start, .. } => params[start].as_u64().unwrap_or(0) as usize,
+            Self::Page { page, .. } => params[page].as_u64().unwrap_or(0) as usize * size,
+        }
+    }
+
+    fn with_offset(self, params: &Value, offset: usize, size: usize) -> Value {
+        let mut params = params.clone();
+        match self {
+            Self::Start { start, .. } => params[start] = json!(offset),
+            Self::Page { page, .. } => params[page] = json!(offset / size),
+        }
+        params
+    }
+}
+
+/// Walk offset pages until a page reports no `has_next` or the result cap is reached
+///
+/// Pages are requested `OFFSET_CONCURRENCY` at a time and handed to `on_page`
+/// in order. Results are capped at `max_results` (default `MAX_OFFSET_RESULTS`);
+/// a capped run keeps `has_next` and `next_offset` so it can be resumed, or
+/// `next_page` for `OffsetParams::Page`, which restarts at the page the cap fell in.
+pub async fn follow_offsets<F, Fut>(
+    params: &Value,
+    style: OffsetParams,
+    max_results: Option<usize>,
+    fetch: F,
+    mut on_page: impl FnMut(Value) -> Result<()>,
+) -> Result<PaginationInfo>
+where
+    F: Fn(Value) -> Fut,
+    Fut: Future<Output = Result<Value>> + Send + 'static,
+{
+    let params = pin_time_range(params)?;
+    let size = params[style.size_key()].as_u64().unwrap_or(100).max(1) as usize;
+    let first = style.offset(&params, size);
+    let limit = max_results.unwrap_or(MAX_OFFSET_RESULTS);
+
+    let mut next = first;
+    let mut fetched = 0;
+    let mut done = false;
+
+    while !done && fetched < limit {
+        let wave = (limit - fetched).div_ceil(size).min(OFFSET_CONCURRENCY);
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for i in 0..wave {
+            let page_params = style.with_offset(&params, next + i * size, size);
+            let future = fetch(page_params);
+            tasks.spawn(async move { (i, future.await) });
+        }
+
+        let mut pages: Vec<(usize, Result<Value>)> = tasks.join_all().await;
+        pages.sort_by_key(|(i, _)| *i);
+
+        for (_, page) in pages {
+            let mut page = page?;
+            let data = page.get_mut("data").and_then(Value::as_array_mut);
+            let returned = data.as_ref().map_or(0, |d| d.len());
+
+            if let Some(data) = data {
+                data.truncate(limit - fetched);
+            }
+            fetched += returned.min(limit - fetched);
+            next += size;
+            done = returned == 0 || page["pagination"]["has_next"] != true;
+
+            on_page(page)?;
+            if done || fetched >= limit {
+                break;
+            }
+        }
+    }
+
+    let mut pagination = PaginationInfo::from_offset(first + fetched, first, size);
+    pagination.total = fetched;
+    pagination.has_next = !done;
+    match style {
+        OffsetParams::Start { .. } => pagination.next_offset = (!done).then_some(first + fetched),
+        OffsetParams::Page { .. } => {
+            pagination.next_offset = None;
+            pagination.next_page = (!done).then_some((first + fetched) / size);
+        }
+    }
+
+    Ok(pagination)
+}
+
+/// Resolve relative `from`/`to` once so every page queries the same window
+fn pin_time_range(params: &Value) -> Result<Value> {
+    let mut params = params.clone();
+    for key in ["from", "to"] {
+        if let Some(time) = params[key].as_str() {
+            params[key] = json!(parse_time(time)?.to_string());
+        }
+    }
+    Ok(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn page(ids: std::ops::Range<usize>, next: Option<&str>) -> Value {
+        let data: Vec<Value> = ids.map(|i| json!({ "id": i })).collect();
+        let pagination = PaginationInfo::from_cursor(data.len(), 2, next.map(str::to_string));
+        json!({ "data": data, "pagination": pagination })
+    }
+
+    #[tokio::test]
+    async fn test_follow_cursor_until_exhausted() {
+        let requests = Mutex::new(Vec::new());
+        let mut seen = Vec::new();
+
+        let pagination = follow_cursor(
+            &json!({ "from": "1 hour ago", "to": "now", "limit": 2 }),
+            None,
+            |params| {
+                requests.lock().unwrap().push(params.clone());
+                async move {
+                    Ok(match params["cursor"].as_str() {
+                        None => page(0..2, Some("c1")),
+                        Some("c1") => page(2..4, Some("c2")),
+                        _ => page(4..5, None),
+                    })
+                }
+            },
+            |page| {
+                seen.extend(page["data"].as_array().unwrap().clone());
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(seen.len(), 5);
+        assert_eq!(pagination.total, 5);
+        assert_eq!(pagination.page, 2);
+        assert!(!pagination.has_next);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0]["from"], requests[2]["from"]);
+        assert!(requests[0]["from"].as_str().unwrap().parse::<i64>().is_ok());
+    }
+
+    fn offset_page(start: usize, count: usize, total: usize) -> Value {
+        let end = (start + count).min(total);
+        let data: Vec<Value> = (start..end).map(|i| json!({ "id": i })).collect();
+        let pagination = PaginationInfo::from_offset_open(start, count, data.len());
+        json!({ "data": data, "pagination": pagination })
+    }
+
+    #[tokio::test]
+    async fn test_follow_offsets_in_order() {
+        let mut seen = Vec::new();
+
+        let pagination = follow_offsets(
+            &json!({ "start": 0, "count": 10 }),
+            OffsetParams::Start {
+                start: "start",
+                count: "count",
+            },
+            None,
+            |params| async move {
+                let start = params["start"].as_u64().unwrap() as usize;
+                Ok(offset_page(start, 10, 95))
+            },
+            |page| {
+                seen.extend(page["data"].as_array().unwrap().clone());
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        let ids: Vec<u64> = seen.iter().map(|v| v["id"].as_u64().unwrap()).collect();
+        assert_eq!(ids, (0..95).collect::<Vec<_>>());
+        assert_eq!(pagination.total, 95);
+        assert!(!pagination.has_next);
+    }
+
+    #[tokio::test]
+    async fn test_follow_offsets_page_numbers_capped() {
+        let mut seen = Vec::new();
+
+        let pagination = follow_offsets(
+            &json!({ "page": 1, "page_size": 10 }),
+            OffsetParams::Page {
+                page: "page",
+                size: "page_size",
+            },
+            Some(25),
+            |params| async move {
+                let page = params["page"].as_u64().unwrap() as usize;
+                Ok(offset_page(page * 10, 10, 1000))
+            },
+            |page| {
+                seen.extend(page["data"].as_array().unwrap().clone());
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(seen.len(), 25);
+        assert_eq!(seen[0]["id"], 10);
+        assert_eq!(pagination.total, 25);
+        assert!(pagination.has_next);
+        // Items 30-34 came from page 3, so resuming starts there rather than at offset 35
+        assert_eq!(pagination.next_offset, None);
+        assert_eq!(pagination.next_page, Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_follow_cursor_max_results() {
+        let mut seen = Vec::new();
+
+        let pagination = follow_cursor(
+            &json!({ "limit": 2 }),
+            Some(3),
+            |params| async move {
+                Ok(match params["cursor"].as_str() {
+                    None => page(0..2, Some("c1")),
+                    _ => {
+                        assert_eq!(params["limit"], 1);
+                        page(2..3, Some("c2"))
+                    }
+                })
+            },
+            |page| {
+                seen.extend(page["data"].as_array().unwrap().clone());
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(seen.len(), 3);
+        assert_eq!(pagination.total, 3);
+        assert_eq!(pagination.next_cursor.as_deref(), Some("c2"));
+    }
+}
@@ -32,6 +32,8 @@ impl RumHandler {
             .await?;
 
         let tag_filter = handler.extract_tag_filter(params, &client);
+        // Custom attributes are only kept when --fields or --query may select them
+        let keep_attributes = params["attributes"].as_bool().unwrap_or(false);
 
         let events: Vec<Value> = response
             .data
@@ -176,19 +178,20 @@ impl RumHandler {
                 {
                     entry["tags"] = json!(tags_vec);
                 }
+                if keep_attributes
+                    && let Some(custom) = attrs.and_then(|a| a.attributes.as_ref())
+                    && !custom.is_empty()
+                {
+                    entry["attributes"] = json!(custom);
+                }
 
                 entry
             })
             .collect();
 
-        let has_cursor = response
-            .meta
-            .as_ref()
-            .and_then(|m| m.page.as_ref())
-            .and_then(|p| p.after.as_ref())
-            .is_some();
+        let next_cursor = response.meta.and_then(|m| m.page).and_then(|p| p.after);
 
-        let pagination = PaginationInfo::from_cursor(events.len(), limit as usize, has_cursor);
+        let pagination = PaginationInfo::from_cursor(events.len(), limit as usize, next_cursor);
 
         Ok(json!({
             "data": events,
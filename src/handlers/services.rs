@@ -3,7 +3,7 @@ use std::sync::Arc;
 
 use crate::datadog::DatadogClient;
 use crate::error::Result;
-use crate::handlers::common::{ParameterParser, ResponseFormatter};
+use crate::handlers::common::{PaginationInfo, ParameterParser, ResponseFormatter};
 
 pub struct ServicesHandler;
 
@@ -120,7 +120,8 @@ impl ServicesHandler {
             })
             .collect();
 
-        let pagination = handler.format_pagination(page as usize, page_size as usize, data.len());
+        let pagination =
+            PaginationInfo::from_page_open(page as usize, page_size as usize, data.len());
 
         let meta = json!({
             "filter_env": filter_env,
@@ -128,7 +129,11 @@ impl ServicesHandler {
             "next": response.links.as_ref().and_then(|l| l.next.clone())
         });
 
-        Ok(handler.format_list(json!(data), Some(pagination), Some(meta)))
+        Ok(handler.format_list(
+            json!(data),
+            Some(serde_json::to_value(pagination)?),
+            Some(meta),
+        ))
     }
 }
 
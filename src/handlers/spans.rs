@@ -109,13 +109,11 @@ impl SpansHandler {
             })
             .collect();
 
-        let has_cursor = response
-            .get("meta")
-            .and_then(|m| m.get("page"))
-            .and_then(|p| p.get("after"))
-            .is_some();
+        let next_cursor = response["meta"]["page"]["after"]
+            .as_str()
+            .map(|s| s.to_string());
 
-        let pagination = PaginationInfo::from_cursor(data.len(), limit as usize, has_cursor);
+        let pagination = PaginationInfo::from_cursor(data.len(), limit as usize, next_cursor);
 
         Ok(json!({
             "data": data,
@@ -7,9 +7,10 @@ async fn main() {
 
     init_logging(cli.verbose);
 
+    let format = cli.format.clone();
     if let Err(e) = datadog_cli::cli::run(cli).await {
-        eprintln!("Error: {}", e);
-        process::exit(1);
+        datadog_cli::cli::report_error(&e, format.as_deref());
+        process::exit(e.exit_code());
     }
 }
 
@@ -25,6 +25,45 @@ pub fn parse_time(input: &str) -> Result<i64> {
     )))
 }
 
+/// Parse a duration such as `90`, `30s`, `5m`, `1h`, `1d` or `1w` into seconds
+pub fn parse_duration(input: &str) -> Result<i64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+
+    let multiplier = match unit.trim() {
+        "" | "s" | "sec" | "secs" => 1,
+        "m" | "min" | "mins" => 60,
+        "h" | "hr" | "hour" | "hours" => 3600,
+        "d" | "day" | "days" => 86400,
+        "w" | "week" | "weeks" => 7 * 86400,
+        _ => 0,
+    };
+
+    match amount.parse::<i64>() {
+        Ok(amount) if amount > 0 && multiplier > 0 => {
+            amount.checked_mul(multiplier).ok_or_else(|| {
+                DatadogError::InvalidInput(format!("Duration '{}' is too large", input))
+            })
+        }
+        _ => Err(DatadogError::InvalidInput(format!(
+            "Invalid duration '{}': use a number with s, m, h, d or w, e.g. 5m",
+            input
+        ))),
+    }
+}
+
+/// Format seconds with the largest unit that divides them evenly, e.g. `1w`, `36h`
+pub fn format_duration(seconds: i64) -> String {
+    [("w", 7 * 86400), ("d", 86400), ("h", 3600), ("m", 60)]
+        .into_iter()
+        .find(|(_, unit)| seconds != 0 && seconds % unit == 0)
+        .map(|(suffix, unit)| format!("{}{}", seconds / unit, suffix))
+        .unwrap_or_else(|| format!("{}s", seconds))
+}
+
 pub fn format_timestamp(timestamp: i64) -> String {
     DateTime::from_timestamp(timestamp, 0)
         .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
@@ -50,6 +89,30 @@ pub fn truncate_stack_trace(stack: &str, max_lines: usize) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90").unwrap(), 90);
+        assert_eq!(parse_duration("30s").unwrap(), 30);
+        assert_eq!(parse_duration("5m").unwrap(), 300);
+        assert_eq!(parse_duration("1h").unwrap(), 3600);
+        assert_eq!(parse_duration("1d").unwrap(), 86400);
+        assert_eq!(parse_duration("2w").unwrap(), 14 * 86400);
+
+        for bad in ["", "m", "0m", "5x", "-5m", "1.5h", "99999999999999999999"] {
+            assert!(parse_duration(bad).is_err(), "{}", bad);
+        }
+        assert!(
+            parse_duration("99999999999999999w")
+                .unwrap_err()
+                .to_string()
+                .contains("too large")
+        );
+
+        for spec in ["45s", "5m", "36h", "1d", "2w"] {
+            assert_eq!(format_duration(parse_duration(spec).unwrap()), spec);
+        }
+    }
+
     #[test]
     fn test_parse_time_now() {
         let result = parse_time("now");